@@ -1,8 +1,9 @@
 use itertools::PeekingNext;
 use num::{self, integer::Roots};
 use std::{
-    cmp,
-    ops::{Add, Sub, Div, Mul}, iter::Sum,
+    cmp, fmt,
+    str::FromStr,
+    ops::{Add, Sub, Div, Mul, Neg}, iter::Sum,
 };
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -50,6 +51,37 @@ impl Direction {
     }
 }
 
+// The three lines of the grid, named by their upward direction.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Axis {
+    N,
+    NE,
+    SE,
+}
+
+impl Axis {
+    pub fn direction(&self) -> Direction {
+        match *self {
+            Axis::N => Direction::N,
+            Axis::NE => Direction::NE,
+            Axis::SE => Direction::SE,
+        }
+    }
+}
+
+// Axis a run of consecutive fields lies on, in either order along it. None for
+// fewer than two fields, gaps, or fields not on one line.
+pub fn run_axis(run: &[HexCoord]) -> Option<Axis> {
+    let step = *run.get(1)? - run[0];
+    let axis = [Axis::N, Axis::NE, Axis::SE].into_iter().find(|a| {
+        let v = a.direction().dir_vec();
+        step == v || step == a.direction().opposite().dir_vec()
+    })?;
+    run.windows(2)
+        .all(|w| w[1] - w[0] == step)
+        .then_some(axis)
+}
+
 pub fn distance_squared(p0: &Point, p1: &Point) -> f32 {
     norm_squared(&Point(p0.0 - p1.0, p0.1 - p1.1))
 }
@@ -61,6 +93,17 @@ pub fn norm_squared(p: &Point) -> f32 {
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct Point(pub f32, pub f32);
 
+impl Point {
+    // `a` at t = 0, `b` at t = 1.
+    pub fn lerp(a: Point, b: Point, t: f32) -> Point {
+        a + (b - a) * t
+    }
+
+    pub fn distance(&self, other: &Point) -> f32 {
+        distance_squared(self, other).sqrt()
+    }
+}
+
 impl Add for Point {
     type Output = Point;
 
@@ -102,6 +145,14 @@ impl Mul<f32> for Point {
     }
 }
 
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Self(-self.0, -self.1)
+    }
+}
+
 impl From<HexCoord> for Point {
     fn from(value: HexCoord) -> Self {
         Point(
@@ -136,7 +187,42 @@ impl From<HexCoord> for HexCoordF {
     }
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+impl Add for HexCoordF {
+    type Output = HexCoordF;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        HexCoordF(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl Sub for HexCoordF {
+    type Output = HexCoordF;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        HexCoordF(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl Mul<f32> for HexCoordF {
+    type Output = HexCoordF;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        HexCoordF(self.0 * rhs, self.1 * rhs)
+    }
+}
+
+impl Neg for HexCoordF {
+    type Output = HexCoordF;
+
+    fn neg(self) -> Self::Output {
+        HexCoordF(-self.0, -self.1)
+    }
+}
+
+// Number of elements of D6, the symmetry group of the hexagonal board.
+pub const N_SYMMETRIES: u8 = 12;
+
+#[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Debug, Copy, Clone)]
 pub struct HexCoord(pub i8, pub i8);
 
 impl HexCoord {
@@ -144,6 +230,30 @@ impl HexCoord {
         HexCoord(x, y)
     }
 
+    // Cube coordinates (x, y, z) with x + y + z == 0; the hex distance to the
+    // origin is max(|x|, |y|, |z|).
+    pub fn to_cube(&self) -> (i32, i32, i32) {
+        let (q, r) = (self.0 as i32, self.1 as i32);
+        (q, -r, r - q)
+    }
+
+    // None if the cube coordinate is invalid or not on a board of the given radius.
+    pub fn from_cube(x: i32, y: i32, z: i32, hex_radius: i32) -> Option<HexCoord> {
+        if x + y + z != 0 {
+            return None;
+        }
+        let c = HexCoord(i8::try_from(x).ok()?, i8::try_from(-y).ok()?);
+        c.on_hex_board(hex_radius).then_some(c)
+    }
+
+    // Yinsh boards are hexagons of the given radius without the six corners.
+    pub fn on_hex_board(&self, hex_radius: i32) -> bool {
+        let (x, y) = (self.0 as i32, self.1 as i32);
+        let dist = x.abs().max(y.abs()).max((x - y).abs());
+        let corner = x == 0 || y == 0 || x == y;
+        dist < hex_radius || (dist == hex_radius && !corner)
+    }
+
     pub fn cartesian_sq_norm(&self) -> f32 {
         norm_squared(&self.clone().into())
     }
@@ -237,6 +347,25 @@ impl HexCoord {
         self.range_iter(&to)
             .and_then(move |iter| Some(iter.skip(1).take_while(move |x| *x != to)))
     }
+
+    // rotate clockwise by 60 degrees about the origin: N -> NE -> SE -> ...
+    pub fn rotate60(&self) -> HexCoord {
+        HexCoord(self.1, self.1 - self.0)
+    }
+
+    // mirror along the NE-SW axis
+    pub fn reflect(&self) -> HexCoord {
+        HexCoord(self.1, self.0)
+    }
+
+    // `sym` in 0..6 rotates by sym * 60 degrees, 6..12 mirrors first
+    pub fn transform(&self, sym: u8) -> HexCoord {
+        let mut c = if sym >= 6 { self.reflect() } else { *self };
+        for _ in 0..sym % 6 {
+            c = c.rotate60();
+        }
+        c
+    }
 }
 
 impl From<(i8, i8)> for HexCoord {
@@ -245,6 +374,33 @@ impl From<(i8, i8)> for HexCoord {
     }
 }
 
+// "q,r", a stable key for maps and debug output; unrelated to the game notation.
+impl fmt::Display for HexCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.0, self.1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCoordError {
+    MissingSeparator,
+    InvalidNumber(String),
+}
+
+impl FromStr for HexCoord {
+    type Err = ParseCoordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (q, r) = s.split_once(',').ok_or(ParseCoordError::MissingSeparator)?;
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<i8>()
+                .map_err(|_| ParseCoordError::InvalidNumber(v.to_string()))
+        };
+        Ok(HexCoord(parse(q)?, parse(r)?))
+    }
+}
+
 impl Add for HexCoord {
     type Output = HexCoord;
 
@@ -301,6 +457,29 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn test_point_arithmetic() {
+        let (a, b) = (Point(1., 2.), Point(3., -2.));
+        assert_eq!(a + b, Point(4., 0.));
+        assert_eq!(a - b, Point(-2., 4.));
+        assert_eq!(a * 2., Point(2., 4.));
+        assert_eq!(b / 2., Point(1.5, -1.));
+        assert_eq!(-a, Point(-1., -2.));
+        assert_eq!(a.distance(&Point(4., 6.)), 5.);
+
+        assert_eq!(Point::lerp(a, b, 0.), a);
+        assert_eq!(Point::lerp(a, b, 0.5), Point(2., 0.));
+        assert_eq!(Point::lerp(a, b, 1.), b);
+
+        let (h, k) = (HexCoordF(1., 2.), HexCoordF(0.5, -1.));
+        assert_eq!(h + k, HexCoordF(1.5, 1.));
+        assert_eq!(h - k, HexCoordF(0.5, 3.));
+        assert_eq!(h * 2., HexCoordF(2., 4.));
+        assert_eq!(-h, HexCoordF(-1., -2.));
+        // both map to the same point
+        assert_eq!(Point::from(h + k), Point::from(h) + Point::from(k));
+    }
+
     fn create_vec<T>(pos: T, dir: T, n: usize) -> Vec<HexCoord>
     where
         T: Into<HexCoord> + Copy,
@@ -332,6 +511,62 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_run_axis() {
+        let run = |dx: i8, dy: i8| -> Vec<HexCoord> {
+            (0..5).map(|i| HexCoord::new(i * dx - 1, i * dy)).collect()
+        };
+        assert_eq!(run_axis(&run(0, 1)), Some(Axis::N));
+        assert_eq!(run_axis(&run(1, 1)), Some(Axis::NE));
+        assert_eq!(run_axis(&run(1, 0)), Some(Axis::SE));
+        assert_eq!(run_axis(&run(0, -1)), Some(Axis::N));
+        assert_eq!(run_axis(&run(-1, 0)), Some(Axis::SE));
+
+        let mut bent = run(0, 1);
+        bent[4] = HexCoord::new(0, 3);
+        assert_eq!(run_axis(&bent), None);
+        assert_eq!(run_axis(&run(1, -1)), None);
+        assert_eq!(run_axis(&run(0, 2)), None);
+        assert_eq!(run_axis(&run(0, 1)[..1]), None);
+    }
+
+    #[test]
+    fn test_cube_round_trip() {
+        let board = crate::core::board::Board::new();
+        let radius = board.hex_radius();
+        for c in board.board_coords() {
+            let (x, y, z) = c.to_cube();
+            assert_eq!(x + y + z, 0);
+            assert_eq!(HexCoord::from_cube(x, y, z, radius), Some(c));
+        }
+        assert_eq!(HexCoord::new(1, 1).to_cube(), (1, -1, 0));
+        assert_eq!(HexCoord::from_cube(1, 1, 1, radius), None);
+        // corner and outside of the board
+        assert_eq!(HexCoord::from_cube(0, -5, 5, radius), None);
+        assert_eq!(HexCoord::from_cube(6, -3, -3, radius), None);
+        assert_eq!(HexCoord::from_cube(4, 0, -4, radius), Some(HexCoord::new(4, 0)));
+        assert_eq!(HexCoord::from_cube(4, 0, -4, 4), None);
+    }
+
+    #[test]
+    fn test_symmetry_transforms() {
+        let dirs = Direction::all();
+        for (i, d) in dirs.iter().enumerate() {
+            assert_eq!(d.dir_vec().rotate60(), dirs[(i + 1) % 6].dir_vec());
+        }
+        let c = HexCoord::new(2, -1);
+        assert_eq!(c.transform(0), c);
+        assert_eq!(c.transform(6), c.reflect());
+        assert_eq!(c.reflect().reflect(), c);
+        for sym in 0..N_SYMMETRIES {
+            let t = c.transform(sym);
+            assert!((t.cartesian_sq_norm() - c.cartesian_sq_norm()).abs() < 1e-5);
+        }
+        let images: std::collections::HashSet<_> =
+            (0..N_SYMMETRIES).map(|s| c.transform(s)).collect();
+        assert_eq!(images.len(), 12);
+    }
+
     #[test]
     fn test_between_iter() {
         assert!(HexCoord::new(1, 1)
@@ -415,4 +650,28 @@ mod test {
             assert_eq!(from.range_iter(&to).unwrap().collect::<Vec<_>>(), res);
         }
     }
+
+    #[test]
+    fn test_string_key_round_trip() {
+        for c in [
+            HexCoord::new(0, 0),
+            HexCoord::new(3, -2),
+            HexCoord::new(-4, -1),
+            HexCoord::new(-5, 5),
+        ] {
+            assert_eq!(c.to_string().parse::<HexCoord>(), Ok(c));
+        }
+        assert_eq!(HexCoord::new(-4, -1).to_string(), "-4,-1");
+        assert_eq!(" 2, -3".parse::<HexCoord>(), Ok(HexCoord::new(2, -3)));
+
+        assert_eq!(
+            "2;3".parse::<HexCoord>(),
+            Err(ParseCoordError::MissingSeparator)
+        );
+        assert_eq!(
+            "a,3".parse::<HexCoord>(),
+            Err(ParseCoordError::InvalidNumber("a".to_string()))
+        );
+        assert!("1,2,3".parse::<HexCoord>().is_err());
+    }
 }
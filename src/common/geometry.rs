@@ -0,0 +1,83 @@
+use crate::common::coord::*;
+
+// Boundary points count as inside for all shapes.
+pub fn point_in_circle(pt: Point, center: Point, radius: f32) -> bool {
+    distance_squared(&pt, &center) <= radius.powi(2)
+}
+
+// Rectangle centered on the segment from `start` along the unit vector `dir`.
+pub fn point_in_oriented_rect(
+    pt: Point,
+    start: Point,
+    dir: Point,
+    length: f32,
+    half_height: f32,
+) -> bool {
+    let diff = pt - start;
+    let proj = diff.0 * dir.0 + diff.1 * dir.1;
+    if proj < 0. || proj > length {
+        return false;
+    }
+    norm_squared(&(diff - dir * proj)) <= half_height.powi(2)
+}
+
+// Closest field of a board with the given hex radius to `world` and its squared
+// distance, if it is within `radius`.
+pub fn nearest_coord(world: Point, radius: f32, hex_radius: i32) -> Option<(HexCoord, f32)> {
+    let (coord, sq_dist) = HexCoord::closest_coord_to_point(&world);
+    if coord.on_hex_board(hex_radius) && sq_dist <= radius.powi(2) {
+        Some((coord, sq_dist))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_point_in_circle() {
+        let center = Point(1., 1.);
+        assert!(point_in_circle(center, center, 0.5));
+        assert!(point_in_circle(Point(1.5, 1.), center, 0.5));
+        assert!(point_in_circle(Point(1., 0.5), center, 0.5));
+        assert!(!point_in_circle(Point(1.51, 1.), center, 0.5));
+        assert!(!point_in_circle(Point(1.4, 1.4), center, 0.5));
+    }
+
+    #[test]
+    fn test_point_in_oriented_rect() {
+        let start = Point(0., 0.);
+        let dir = Point(1., 0.);
+        assert!(point_in_oriented_rect(Point(1., 0.), start, dir, 2., 0.5));
+        assert!(point_in_oriented_rect(start, start, dir, 2., 0.5));
+        assert!(point_in_oriented_rect(Point(2., 0.5), start, dir, 2., 0.5));
+        assert!(point_in_oriented_rect(Point(0., -0.5), start, dir, 2., 0.5));
+        assert!(!point_in_oriented_rect(Point(-0.01, 0.), start, dir, 2., 0.5));
+        assert!(!point_in_oriented_rect(Point(2.01, 0.), start, dir, 2., 0.5));
+        assert!(!point_in_oriented_rect(Point(1., 0.51), start, dir, 2., 0.5));
+
+        // diagonal
+        let s = 0.5_f32.sqrt();
+        let dir = Point(s, s);
+        assert!(point_in_oriented_rect(Point(1., 1.), start, dir, 2., 0.1));
+        assert!(!point_in_oriented_rect(Point(1., 0.), start, dir, 2., 0.1));
+    }
+
+    #[test]
+    fn test_nearest_coord() {
+        let hex_radius = 5;
+        let c = HexCoord::new(1, 2);
+        let pt = Point::from(c);
+
+        assert_eq!(nearest_coord(pt, 0., hex_radius), Some((c, 0.)));
+        let (coord, d) = nearest_coord(pt + Point(0.1, 0.), 0.2, hex_radius).unwrap();
+        assert_eq!(coord, c);
+        assert!((d - 0.01).abs() < 1e-6);
+        assert_eq!(nearest_coord(pt + Point(0.3, 0.), 0.2, hex_radius), None);
+
+        // closest lattice point is a missing corner of the board
+        assert_eq!(nearest_coord(Point::from(HexCoord::new(0, 5)), 1., hex_radius), None);
+    }
+}
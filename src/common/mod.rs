@@ -1 +1,3 @@
-pub mod coord;
\ No newline at end of file
+pub mod coord;
+pub mod geometry;
+pub mod notation;
\ No newline at end of file
@@ -0,0 +1,45 @@
+use crate::common::coord::HexCoord;
+
+// Official Yinsh labels on the standard board: files a-k left to right, ranks
+// starting at 1. The center field (0, 0) is f6.
+const FILE_OFFSET: i32 = 5;
+const RANK_OFFSET: i32 = 6;
+
+pub fn coord_label(c: &HexCoord) -> String {
+    let file = (b'a' as i32 + c.0 as i32 + FILE_OFFSET) as u8 as char;
+    format!("{}{}", file, c.1 as i32 + RANK_OFFSET)
+}
+
+pub fn parse_label(label: &str) -> Option<HexCoord> {
+    let mut chars = label.chars();
+    let file = chars.next()?;
+    if !file.is_ascii_lowercase() {
+        return None;
+    }
+    let rank: i32 = chars.as_str().parse().ok()?;
+    let x = i8::try_from(file as i32 - 'a' as i32 - FILE_OFFSET).ok()?;
+    let y = i8::try_from(rank - RANK_OFFSET).ok()?;
+    Some(HexCoord::new(x, y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(coord_label(&HexCoord::new(0, 0)), "f6");
+        assert_eq!(coord_label(&HexCoord::new(-5, -4)), "a2");
+        assert_eq!(coord_label(&HexCoord::new(5, 4)), "k10");
+        assert_eq!(parse_label("k10"), Some(HexCoord::new(5, 4)));
+        assert_eq!(parse_label("f"), None);
+        assert_eq!(parse_label("6f"), None);
+
+        for x in -5..=5 {
+            for y in -5..=5 {
+                let c = HexCoord::new(x, y);
+                assert_eq!(parse_label(&coord_label(&c)), Some(c));
+            }
+        }
+    }
+}
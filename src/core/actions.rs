@@ -1,11 +1,14 @@
+use std::hash::{Hash, Hasher};
+
 use enum_dispatch::enum_dispatch;
 
 use crate::common::coord::*;
+use super::board::POINTS_TO_WIN;
 use super::command::*;
 use super::{state::*, entities::*};
 
 #[enum_dispatch(Command)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Action {
     PlaceRing,
     PlaceMarker,
@@ -14,23 +17,40 @@ pub enum Action {
     RemoveRing,
 }
 
-#[derive(Debug, Clone)]
+impl Action {
+    // The field the player clicks to select this action.
+    pub fn primary_coord(&self) -> HexCoord {
+        self.coord()
+    }
+
+    // Where the moved piece comes from, for actions that move one.
+    pub fn source_coord(&self) -> Option<HexCoord> {
+        match self {
+            Action::MoveRing(a) => Some(a.from),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PlaceRing {
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PlaceMarker {
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MoveRing {
     pub from: HexCoord,
     pub to: HexCoord,
     pub player: Player,
 }
 
+// Equal when the same markers are removed: the fields in any order, whatever
+// the run's index in the run list and the clicked field.
 #[derive(Debug, Clone)]
 pub struct RemoveRun {
     pub run_idx: usize,
@@ -38,7 +58,29 @@ pub struct RemoveRun {
     pub coord: HexCoord,
 }
 
-#[derive(Debug, Clone)]
+impl RemoveRun {
+    fn sorted_run(&self) -> Vec<HexCoord> {
+        let mut run = self.run.clone();
+        run.sort();
+        run
+    }
+}
+
+impl PartialEq for RemoveRun {
+    fn eq(&self, other: &Self) -> bool {
+        self.sorted_run() == other.sorted_run()
+    }
+}
+
+impl Eq for RemoveRun {}
+
+impl Hash for RemoveRun {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sorted_run().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RemoveRing {
     pub coord: HexCoord,
     pub player: Player,
@@ -59,9 +101,11 @@ impl Command for PlaceRing {
 
         state.next_player();
         state.history.push(Action::from(self.clone()));
+        state.record_position();
     }
 
     fn undo(&self, state: &mut State) {
+        state.forget_position();
         state.new_action();
         state.remove_ring(&state.current_player.clone(), &self.coord);
         state.set_phase(Phase::PlaceRing);
@@ -77,6 +121,7 @@ impl Command for PlaceMarker {
     fn is_legal(&self, state: &State) -> bool {
         state.at_phase(&Phase::PlaceMarker)
             && state.board.player_ring_at(&self.coord, &state.current_player)
+            && state.board.markers_remaining() > 0
     }
 
     fn execute(&self, state: &mut State) {
@@ -84,9 +129,11 @@ impl Command for PlaceMarker {
         state.place_marker(&state.current_player.clone(), &self.coord);
         state.set_phase(Phase::MoveRing(self.coord));
         state.history.push(Action::from(self.clone()));
+        state.record_position();
     }
 
     fn undo(&self, state: &mut State) {
+        state.forget_position();
         state.new_action();
         state.place_ring(&state.current_player.clone(), &self.coord);
         state.set_phase(Phase::PlaceMarker);
@@ -115,11 +162,28 @@ impl Command for MoveRing {
         state.move_ring(&state.current_player.clone(), &self.from, &self.to, false, true);
         state.flip_markers(&self.from, &self.to);
 
-        state.compute_runs();
+        let mut changed = vec![self.from];
+        changed.extend(state.last_state_change.iter().filter_map(|sc| match sc {
+            StateChange::MarkerFlipped(c) => Some(*c),
+            _ => None,
+        }));
+        let runs_before = Player::all().map(|p| state.player_runs(p).clone());
+        state.update_runs_at(&changed);
+        for (player, before) in Player::all().into_iter().zip(runs_before) {
+            let formed: Vec<Vec<HexCoord>> = state
+                .player_runs(player)
+                .iter()
+                .filter(|r| !before.contains(r))
+                .cloned()
+                .collect();
+            state
+                .last_state_change
+                .extend(formed.into_iter().map(|r| StateChange::RunFormed(player, r)));
+        }
 
-        if state.board.runs(&state.current_player).len() > 0 {
+        if state.has_run(&state.current_player) {
             state.set_phase(Phase::RemoveRun);
-        } else if state.board.runs(&state.current_player.other()).len() > 0 {
+        } else if state.has_run(&state.current_player.other()) {
             state.set_phase(Phase::RemoveRun);
             state.next_player();
         } else {
@@ -128,9 +192,11 @@ impl Command for MoveRing {
         }
 
         state.history.push(Action::from(self.clone()));
+        state.record_position();
     }
 
     fn undo(&self, state: &mut State) {
+        state.forget_position();
         state.new_action();
         state.move_ring(&state.current_player.clone(), &self.to, &self.from, true, false);
 
@@ -138,7 +204,11 @@ impl Command for MoveRing {
 
         state.current_player = self.player;
         state.set_phase(Phase::MoveRing(self.from));
+        // runs through the marker under the ring only count once the ring has
+        // moved, as before execute()
         state.compute_runs();
+        state.runs_white.retain(|r| !r.contains(&self.from));
+        state.runs_black.retain(|r| !r.contains(&self.from));
     }
 
     fn coord(&self) -> HexCoord {
@@ -157,12 +227,14 @@ impl Command for RemoveRun {
             state.remove_marker(&state.current_player.clone(), c);
         });
 
-        state.compute_runs();
+        state.update_runs_at(&self.run);
         state.set_phase(Phase::RemoveRing);
         state.history.push(Action::from(self.clone()));
+        state.record_position();
     }
 
     fn undo(&self, state: &mut State) {
+        state.forget_position();
         state.new_action();
         state.set_phase(Phase::RemoveRun);
         self.run.iter().for_each(|c| {
@@ -186,13 +258,33 @@ impl Command for RemoveRing {
     fn execute(&self, state: &mut State) {
         state.new_action();
         state.history.push(Action::from(self.clone()));
+        self.resolve(state);
+        state.record_position();
+    }
+
+    fn undo(&self, state: &mut State) {
+        state.forget_position();
+        state.new_action();
+        state.current_player = self.player;
+        state.dec_score(&self.player);
+        state.set_phase(Phase::RemoveRing);
+
+        state.place_ring(&state.current_player.clone(), &self.coord);
+    }
+
+    fn coord(&self) -> HexCoord {
+        self.coord
+    }
+}
 
+impl RemoveRing {
+    fn resolve(&self, state: &mut State) {
         state.remove_ring(&state.current_player.clone(), &self.coord);
 
         let current_player = state.current_player;
         state.inc_score(&current_player);
 
-        if state.get_score(&current_player) == 3 {
+        if state.get_score(&current_player) == POINTS_TO_WIN {
             state.set_phase(Phase::PlayerWon(current_player));
             return;
         }
@@ -202,36 +294,97 @@ impl Command for RemoveRing {
             return;
         }
 
-        state.next_player();
+        // runs created for the opponent by the ring move are resolved by the opponent,
+        // who then continues with a regular turn
+        if state.last_mover() != Some(current_player.other()) {
+            state.next_player();
+        }
 
         if state.has_run(&state.current_player) {
             state.set_phase(Phase::RemoveRun);
         } else {
             state.set_phase(Phase::PlaceMarker);
         }
-        state.history.push(Action::from(self.clone()));
-    }
-
-    fn undo(&self, state: &mut State) {
-        state.new_action();
-        state.current_player = self.player;
-        state.dec_score(&self.player);
-        state.set_phase(Phase::RemoveRing);
-
-        state.place_ring(&state.current_player.clone(), &self.coord);
-    }
-
-    fn coord(&self) -> HexCoord {
-        self.coord
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::core::board::Board;
+    use crate::core::board::{Board, MARKER_SUPPLY};
 
     use super::*;
 
+    #[test]
+    fn test_action_equality() {
+        let c = HexCoord::new(1, 2);
+        assert_eq!(
+            Action::from(PlaceRing { coord: c }),
+            Action::from(PlaceRing { coord: c })
+        );
+        assert_ne!(
+            Action::from(PlaceRing { coord: c }),
+            Action::from(PlaceMarker { coord: c })
+        );
+        let move_ring = |player| {
+            Action::from(MoveRing {
+                player,
+                from: HexCoord::new(0, 0),
+                to: c,
+            })
+        };
+        assert_ne!(move_ring(Player::White), move_ring(Player::Black));
+
+        let run: Vec<HexCoord> = (-2..3).map(|x| HexCoord::new(x, 0)).collect();
+        let reversed: Vec<HexCoord> = run.iter().rev().copied().collect();
+        let remove = |run_idx, run: &Vec<HexCoord>| {
+            Action::from(RemoveRun {
+                run_idx,
+                run: run.clone(),
+                coord: run[0],
+            })
+        };
+        assert_eq!(remove(0, &run), remove(1, &reversed));
+        let shifted: Vec<HexCoord> = (-1..4).map(|x| HexCoord::new(x, 0)).collect();
+        assert_ne!(remove(0, &run), remove(0, &shifted));
+
+        let moves: std::collections::HashSet<Action> =
+            [remove(0, &run), remove(1, &reversed), remove(0, &shifted)]
+                .into_iter()
+                .collect();
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn test_source_coord() {
+        let (from, to) = (HexCoord::new(0, 0), HexCoord::new(0, 3));
+        let action = Action::from(MoveRing {
+            player: Player::White,
+            from,
+            to,
+        });
+        assert_eq!(action.source_coord(), Some(from));
+        assert_eq!(action.primary_coord(), to);
+
+        let c = HexCoord::new(1, 2);
+        let others = [
+            Action::from(PlaceRing { coord: c }),
+            Action::from(PlaceMarker { coord: c }),
+            Action::from(RemoveRun {
+                run_idx: 0,
+                run: vec![c],
+                coord: c,
+            }),
+            Action::from(RemoveRing {
+                player: Player::White,
+                coord: c,
+            }),
+        ];
+        for action in others {
+            assert_eq!(action.source_coord(), None);
+            assert_eq!(action.primary_coord(), c);
+        }
+    }
+
     #[test]
     fn test_place_ring() {
         let mut state = State::new(Board::new());
@@ -637,6 +790,79 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_remove_run_reports_removed_markers() {
+        let mut state = State::new(Board::new());
+        state.set_phase(Phase::RemoveRun);
+        let run: Vec<HexCoord> = (-2..=2).map(|y| HexCoord::new(1, y)).collect();
+        for c in &run {
+            state.board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        state.compute_runs();
+
+        Action::from(RemoveRun {
+            run: run.clone(),
+            run_idx: 0,
+            coord: run[0],
+        })
+        .execute(&mut state);
+
+        let removed: Vec<StateChange> = run
+            .iter()
+            .map(|c| StateChange::MarkerRemoved(Player::White, *c))
+            .collect();
+        assert_eq!(state.last_state_change, removed);
+    }
+
+    #[test]
+    fn test_marker_supply() {
+        let mut state = State::new(Board::new());
+        let ring = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &ring);
+        let run: Vec<HexCoord> = (-4..=0).map(|x| HexCoord::new(x, -3)).collect();
+        let fields: Vec<HexCoord> = state
+            .board
+            .board_coords()
+            .into_iter()
+            .filter(|c| *c != ring && !run.contains(c))
+            .take(MARKER_SUPPLY - run.len())
+            .collect();
+        for (i, c) in fields.iter().enumerate() {
+            let player = if i % 2 == 0 { Player::Black } else { Player::White };
+            state.board.place_unchecked(&Piece::Marker(player), c);
+        }
+        for c in &run {
+            state.board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        assert_eq!(state.board.total_markers(), MARKER_SUPPLY);
+
+        let place = PlaceMarker { coord: ring };
+        state.set_phase(Phase::PlaceMarker);
+        assert!(!place.is_legal(&state));
+        assert!(state.legal_moves().is_empty());
+
+        state.board.flip_marker(&fields[0]);
+        assert_eq!(state.board.markers_remaining(), 0);
+
+        state.compute_runs();
+        state.set_phase(Phase::RemoveRun);
+        let idx = state
+            .player_runs(Player::White)
+            .iter()
+            .position(|r| *r == run)
+            .unwrap();
+        Action::from(RemoveRun {
+            run: run.clone(),
+            run_idx: idx,
+            coord: run[0],
+        })
+        .execute(&mut state);
+        assert_eq!(state.board.markers_remaining(), run.len());
+
+        state.set_phase(Phase::PlaceMarker);
+        assert!(place.is_legal(&state));
+    }
+
     #[test]
     fn test_remove_run_wrong_phase() {
         let mut state = State::new(Board::new());
@@ -921,4 +1147,132 @@ mod test {
             assert!(state.board.player_ring_at(&c, &player));
         }
     }
-}
\ No newline at end of file
+
+    fn play_first_legal_move(state: &mut State) {
+        let action = state.legal_moves().into_iter().next().unwrap();
+        assert!(action.is_legal(state));
+        action.execute(state);
+    }
+
+    #[test]
+    fn test_two_disjoint_runs_score_twice() {
+        let mut state = State::new(Board::new());
+        state.current_player = Player::White;
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 2));
+        }
+        for c in [(-3, -3), (3, 4), (-4, -1)] {
+            state.board
+                .place_unchecked(&Piece::Ring(Player::White), &HexCoord::from(c));
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(1, -3));
+        state.compute_runs();
+        state.set_phase(Phase::RemoveRun);
+        assert_eq!(state.runs_white.len(), 2);
+
+        play_first_legal_move(&mut state);
+        assert_eq!(state.current_phase, Phase::RemoveRing);
+        play_first_legal_move(&mut state);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.legal_moves().len(), 1);
+        play_first_legal_move(&mut state);
+        play_first_legal_move(&mut state);
+
+        assert_eq!(state.points_white, 2);
+        assert_eq!(state.board.player_rings(Player::White).count(), 1);
+        assert_eq!(state.board.markers().count(), 0);
+        assert_eq!(state.current_player, Player::Black);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.history.len(), 4);
+
+        while state.undo().is_ok() {}
+        assert_eq!(state.points_white, 0);
+        assert_eq!(state.board.player_markers(Player::White).count(), 10);
+        assert_eq!(state.board.player_rings(Player::White).count(), 3);
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+    }
+
+    #[test]
+    fn test_runs_for_both_players_opponent_continues() {
+        let mut state = State::new(Board::new());
+        state.current_player = Player::White;
+        for i in -2..=2 {
+            state.board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+            state.board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(i, 2));
+        }
+        state.board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-3, -3));
+        state.board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(1, -3));
+        state.history.push(Action::from(MoveRing {
+            player: Player::White,
+            from: HexCoord::new(-3, -2),
+            to: HexCoord::new(-3, -3),
+        }));
+        state.compute_runs();
+        state.set_phase(Phase::RemoveRun);
+
+        play_first_legal_move(&mut state);
+        play_first_legal_move(&mut state);
+        assert_eq!(state.current_player, Player::Black);
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+
+        play_first_legal_move(&mut state);
+        play_first_legal_move(&mut state);
+        assert_eq!(state.points_white, 1);
+        assert_eq!(state.points_black, 1);
+        assert_eq!(state.current_player, Player::Black);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+    }
+
+    #[test]
+    fn test_move_ring_reports_formed_runs() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        let flipped = HexCoord::new(0, 1);
+        state.board.place_unchecked(&Piece::Marker(Player::White), &from);
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &flipped);
+        // two lines through the flipped marker, along x and along x = y
+        let row: Vec<HexCoord> = (0..5).map(|x| HexCoord::new(x, 1)).collect();
+        let diagonal: Vec<HexCoord> = (-2..3).map(|i| HexCoord::new(i, i + 1)).collect();
+        for c in row.iter().chain(diagonal.iter()).filter(|c| **c != flipped) {
+            state.board.place_unchecked(&Piece::Marker(Player::White), c);
+        }
+        state.set_phase(Phase::MoveRing(from));
+
+        let action = Action::from(MoveRing {
+            player: Player::White,
+            from,
+            to: HexCoord::new(0, 2),
+        });
+        action.execute(&mut state);
+        let mut formed: Vec<Vec<HexCoord>> = state
+            .last_state_change()
+            .into_iter()
+            .filter_map(|sc| match sc {
+                StateChange::RunFormed(Player::White, run) => Some(run),
+                StateChange::RunFormed(Player::Black, _) => panic!("no black run"),
+                _ => None,
+            })
+            .collect();
+        formed.sort();
+        let mut expected = vec![row, diagonal];
+        expected.sort();
+        assert_eq!(formed, expected);
+        assert!(state.last_move_summary().run_formed);
+
+        state.undo().unwrap();
+        assert!(!state
+            .last_state_change()
+            .iter()
+            .any(|sc| matches!(sc, StateChange::RunFormed(..))));
+    }
+}
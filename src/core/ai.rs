@@ -1,124 +1,290 @@
+use crate::core::actions::Action;
+use crate::core::book::OpeningBook;
 use crate::core::command::Command;
+use crate::core::search::Search;
+use crate::core::symmetry::dedup_symmetric_moves;
 use crate::core::{state::*, entities::*};
-use rand::thread_rng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
 
+// Result of one cooperative step of an AI's turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiStatus {
+    // the AI needs more steps; the state is unchanged
+    Thinking,
+    // the AI has made its move
+    Done,
+}
+
+pub trait Ai {
+    fn turn(&mut self, state: &mut State);
+    // A bounded piece of the turn, so that the caller can keep rendering between
+    // steps. AIs that can't split their search take the whole turn at once.
+    fn step(&mut self, state: &mut State) -> AiStatus {
+        self.turn(state);
+        AiStatus::Done
+    }
+    // Makes the AI's random choices reproducible.
+    fn seed(&mut self, _seed: u64) {}
+    // Switches the side the AI plays, e.g. between games of a match.
+    fn set_player(&mut self, _player: Player) {}
+    // Static evaluation from the AI player's point of view; 0 if it has none.
+    fn evaluate(&self, _state: &State) -> i32 {
+        0
+    }
+}
+
+// Alpha-beta search over a heuristic, `max_depth` plies below each candidate
+// move. It is only random where the search can't tell moves apart: rings are
+// placed on random fields.
 pub struct RandomAI {
-    player: Player,
-    max_depth: u32,
-    pub evaluated_moves: u32,
+    search: Search,
+    book: Option<OpeningBook>,
+    dedup_symmetric: bool,
+    rng: StdRng,
+}
+
+fn player_heuristic(game: &State, player: Player) -> i32 {
+    let n_markers = game.board.player_markers(player).count();
+    let ring_moves = game.board.ring_mobility(player);
+    let points = 100000*game.get_score(&player);
+
+    let run_length = game.board.run_length();
+    let connected_3 = 10*game.board.n_connected_markers(&player, run_length - 2);
+    let connected_4 = 30*game.board.n_connected_markers(&player, run_length - 1);
+    let run = 1000*game.board.maximal_runs(&player).len();
+
+    (n_markers + ring_moves + points + connected_3 + connected_4 + run) as i32
+}
+
+fn heuristic(game: &State, player: Player) -> i32 {
+    player_heuristic(game, player) - player_heuristic(game, player.other())
+}
+
+fn candidate_moves(game: &State, dedup_symmetric: bool) -> Vec<Action> {
+    let moves = game.legal_moves();
+    if dedup_symmetric {
+        dedup_symmetric_moves(game, moves)
+    } else {
+        moves
+    }
 }
 
 impl RandomAI {
     pub fn new(player: Player, max_depth: u32) -> Self {
         RandomAI {
-            max_depth,
-            player,
-            evaluated_moves: 0,
+            search: Search::new(player, max_depth),
+            book: None,
+            dedup_symmetric: false,
+            rng: StdRng::from_entropy(),
         }
     }
 
+    // Only evaluate one root move per class of moves leading to symmetric positions.
+    pub fn with_symmetry_dedup(mut self, flag: bool) -> Self {
+        self.dedup_symmetric = flag;
+        self
+    }
+
+    // Root moves plus searched nodes of the last turn.
+    pub fn evaluated_moves(&self) -> u32 {
+        self.search.evaluated_moves
+    }
+
+    pub fn candidate_moves(&self, game: &State) -> Vec<Action> {
+        candidate_moves(game, self.dedup_symmetric)
+    }
+
+    // Drops moves after which the opponent has more runs than before, i.e. moves
+    // that flip markers into a run for them. All moves are kept if every one does.
+    pub fn avoid_gifted_runs(game: &State, moves: Vec<Action>) -> Vec<Action> {
+        let mut game = game.clone();
+        let opponent = game.current_player.other();
+        let before = game.player_runs(opponent).len();
+        let safe: Vec<Action> = moves
+            .iter()
+            .filter(|action| {
+                game.make(action);
+                let gifted = game.player_runs(opponent).len() > before;
+                game.unmake();
+                !gifted
+            })
+            .cloned()
+            .collect();
+        if safe.is_empty() {
+            moves
+        } else {
+            safe
+        }
+    }
+
+    pub fn with_book(mut self, book: OpeningBook) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    pub fn book_move(&self, game: &State) -> Option<Action> {
+        self.book.as_ref().and_then(|b| b.lookup(game))
+    }
+
     pub fn turn(&mut self, game: &mut State) {
-        println!("AI turn");
-        self.evaluated_moves = 0;
-        let mut game_clone = game.clone();
-        let moves = game.legal_moves();
-        println!("Found legal moves: {:?}", moves.len());
-        if game.at_phase(&Phase::PlaceRing) {
-            let mut rng = thread_rng();
-            let action = moves.choose(&mut rng).unwrap();
+        while self.step(game) == AiStatus::Thinking {}
+    }
+
+    // Book moves and ring placements are made at once, otherwise one root move
+    // is searched per step.
+    pub fn step(&mut self, game: &mut State) -> AiStatus {
+        if let Some(action) = self.book_move(game) {
+            self.search.evaluated_moves = 0;
             action.execute(game);
-            return;
+            return AiStatus::Done;
         }
-
-        let mut best_action = None;
-        let mut best_score : f32 = f32::NEG_INFINITY;
-        self.evaluated_moves += moves.len() as u32;
-        for action in moves {
-            action.execute(&mut game_clone);
-            let score = self.alpha_beta(&mut game_clone, self.max_depth, self.player, None, None);
-            if score > best_score {
-                best_score = score;
-                best_action = Some(action.clone());
+        if game.at_phase(&Phase::PlaceRing) {
+            self.search.evaluated_moves = 0;
+            let moves = self.candidate_moves(game);
+            if let Some(action) = moves.choose(&mut self.rng) {
+                action.execute(game);
             }
-            println!("  >> Eval: {:?}, {}", action, score);
-            action.undo(&mut game_clone);
+            return AiStatus::Done;
         }
-        if let Some(action) = best_action {
-            println!("Found ACTION {:?}", action);
-            action.execute(game);
-            println!("Executed!");
+
+        let (player, dedup) = (self.search.player(), self.dedup_symmetric);
+        let moves = || RandomAI::avoid_gifted_runs(game, candidate_moves(game, dedup));
+        match self.search.step(game, moves, |s| heuristic(s, player)) {
+            None => AiStatus::Thinking,
+            Some(best) => {
+                if let Some(action) = best {
+                    action.execute(game);
+                }
+                AiStatus::Done
+            }
         }
     }
+}
 
+impl Ai for RandomAI {
+    fn turn(&mut self, state: &mut State) {
+        RandomAI::turn(self, state)
+    }
 
-    fn player_heuristic(&self, game: &State, player: Player) -> f32 {
-        let n_markers = game.board.player_markers(player).count();
-        let ring_moves: usize = game.board.player_rings(player)
-            .map(|c| game.board.ring_targets(c).len())
-            .sum::<usize>();
-        let points = 100000*game.get_score(&player);
+    fn step(&mut self, state: &mut State) -> AiStatus {
+        RandomAI::step(self, state)
+    }
 
-        let connected_3 = 10*game.board.n_connected_markers(&player, 3);
-        let connected_4 = 30*game.board.n_connected_markers(&player, 4);
-        let run = 1000*game.board.runs(&player).len();
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
 
-        (n_markers + ring_moves + points + connected_3 + connected_4 + run) as f32
+    fn set_player(&mut self, player: Player) {
+        self.search.set_player(player);
     }
 
-    fn heuristic(&self, game: &State, player: Player) -> f32 {
-        self.player_heuristic(game, player) - self.player_heuristic(game, player.other())
+    fn evaluate(&self, state: &State) -> i32 {
+        heuristic(state, self.search.player())
     }
+}
 
-    fn alpha_beta(&mut self, game: &mut State, depth: u32, ai_player: Player, alpha: Option<f32>, beta: Option<f32>) -> f32 {
-        let mut alpha = alpha.unwrap_or(f32::NEG_INFINITY);
-        let mut beta = beta.unwrap_or(f32::INFINITY);
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::actions::PlaceMarker;
+    use crate::core::board::Board;
+    use crate::core::entities::Piece;
 
-        if game.won_by().is_some() || depth == 0 {
-            return self.heuristic(&game, ai_player);
-        }
+    use super::*;
 
-        if game.current_player == ai_player {
-    //        println!("     >> AI player");
-            let mut best_val = f32::NEG_INFINITY;
-            for m in game.legal_moves() {
-    //            println!("     >> {:?}", m);
-                self.evaluated_moves += 1;
+    #[test]
+    fn test_evaluated_moves_per_turn() {
+        let mut state = State::new(Board::new());
+        let ring = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &ring);
+        state.board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        state.set_phase(Phase::PlaceMarker);
 
-                if !m.is_legal(game) {
-                    panic!("ILLEGAL ACTION {:?}", m);
-                }
-                m.execute(game);
-                let value = self.alpha_beta(game, depth - 1, ai_player, Some(alpha), Some(beta));
-                m.undo(game);
-
-                best_val = best_val.max(value);
-                alpha = alpha.max(best_val);
-                if beta <= alpha {
-                    break;
-                }
-            }
-            return best_val
+        // the only root move is the marker, below it every ring move is searched
+        let mut ai = RandomAI::new(Player::White, 0);
+        ai.turn(&mut state.clone());
+        assert_eq!(ai.evaluated_moves(), 1);
+
+        let mut ai = RandomAI::new(Player::White, 1);
+        let expected = 1 + state.board.ring_targets(&ring).len() as u32;
+        ai.turn(&mut state.clone());
+        assert_eq!(ai.evaluated_moves(), expected);
+        ai.turn(&mut state.clone());
+        assert_eq!(ai.evaluated_moves(), expected);
+    }
+
+    #[test]
+    fn test_turn_is_split_into_steps() {
+        let mut state = State::new(Board::new());
+        let ring = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &ring);
+        state.board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        state.set_phase(Phase::PlaceMarker);
+        Action::from(PlaceMarker { coord: ring }).execute(&mut state);
+        let n_moves = state.legal_moves().len();
+        assert!(n_moves > 1);
+
+        let mut expected = state.clone();
+        RandomAI::new(Player::White, 1).turn(&mut expected);
+
+        let mut ai = RandomAI::new(Player::White, 1);
+        let mut stepped = state.clone();
+        assert_eq!(ai.step(&mut stepped), AiStatus::Thinking);
+        assert_eq!(stepped.history.len(), state.history.len());
+        let mut steps = 1;
+        while ai.step(&mut stepped) == AiStatus::Thinking {
+            steps += 1;
         }
+        assert_eq!(steps + 1, n_moves);
+        assert_eq!(stepped.history.last(), expected.history.last());
+    }
 
-        let mut best_val = f32::INFINITY;
-        for m in game.legal_moves() {
-            self.evaluated_moves += 1;
-            //println!("     >> Human player");
-            //println!("     >> {:?}", m);
-                if !m.is_legal(game) {
-                    panic!("ILLEGAL ACTION {:?}", m);
-                }
-            m.execute(game);
-            let value = self.alpha_beta(game, depth - 1, ai_player, Some(alpha), Some(beta));
-            m.undo(game);
-            best_val = best_val.min(value);
-            beta = beta.min(best_val);
-            if beta <= alpha {
-                break;
-            }
+    // White's ring at (2, -1) can only jump the white marker north, which
+    // completes a black run, or step south to (2, -2).
+    fn gift_position() -> State {
+        let mut state = State::new(Board::new());
+        for x in -2..2 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(x, 0));
+        }
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(2, 0));
+        let from = HexCoord::new(2, -1);
+        state.board.place_unchecked(&Piece::Marker(Player::White), &from);
+        for c in [(3, -1), (1, -1), (3, 0), (1, -2), (2, -3)] {
+            state
+                .board
+                .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::from(c));
         }
-        return best_val
+        state.set_phase(Phase::MoveRing(from));
+        state
+    }
+
+    #[test]
+    fn test_avoids_gifting_runs() {
+        let state = gift_position();
+        let moves = state.legal_moves();
+        assert_eq!(moves.len(), 2);
+        let safe = RandomAI::avoid_gifted_runs(&state, moves);
+        assert_eq!(safe.len(), 1);
+        assert_eq!(safe[0].coord(), HexCoord::new(2, -2));
+
+        let mut played = state.clone();
+        RandomAI::new(Player::White, 1).turn(&mut played);
+        assert!(played.player_runs(Player::Black).is_empty());
+        assert!(played.board.player_ring_at(&HexCoord::new(2, -2), &Player::White));
 
+        // with only gifting moves left the AI still has to move
+        let mut forced = state.clone();
+        forced
+            .board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(2, -2));
+        let moves = forced.legal_moves();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(RandomAI::avoid_gifted_runs(&forced, moves).len(), 1);
     }
 }
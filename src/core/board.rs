@@ -1,67 +1,140 @@
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
-use itertools::Itertools;
-use itertools::PeekingNext;
-
 use crate::common::coord::*;
+use crate::common::geometry::nearest_coord;
+use crate::common::notation::{coord_label, parse_label};
+use crate::core::config::GameConfig;
 use crate::core::entities::*;
+use crate::core::state::Phase;
+use crate::core::zobrist;
+
+pub const RINGS_PER_PLAYER: usize = 5;
+// removed rings needed to win
+pub const POINTS_TO_WIN: usize = 3;
+// Markers in the game box, shared by both players.
+pub const MARKER_SUPPLY: usize = 51;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    PieceOffBoard(HexCoord),
+    MarkersBeforeRings,
+    TooManyRings(Player),
+    // shorter than 3 or longer than the longest line of the board
+    InvalidRunLength(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceError {
+    Occupied(HexCoord),
+    OffBoard(HexCoord),
+    TooManyRings(Player),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    Empty,
+    InvalidRadius(u8),
+    WrongLength { expected: usize, found: usize },
+    InvalidField(HexCoord),
+}
+
+pub const STANDARD_RADIUS: i32 = 5;
+pub const RUN_LENGTH: usize = 5;
 
 #[derive(Clone)]
 pub struct Board {
     board_map: HashMap<HexCoord, Piece>,
-    radius: f32,
+    hex_radius: i32,
+    rings_per_player: usize,
+    run_length: usize,
+    // ring_targets() per ring, cleared whenever a piece is placed or removed
+    target_cache: RefCell<HashMap<HexCoord, Vec<HexCoord>>>,
 }
 
 impl Board {
     pub fn new() -> Self {
+        Board::with_radius(STANDARD_RADIUS)
+    }
+
+    // Hexagonal board with `r` fields from the center to each edge, without the six
+    // corner points (r = 5 is the standard board with 85 fields).
+    pub fn with_radius(r: i32) -> Self {
         Board {
             board_map: HashMap::new(),
-            radius: 4.7,
+            hex_radius: r,
+            rings_per_player: RINGS_PER_PLAYER,
+            run_length: RUN_LENGTH,
+            target_cache: RefCell::new(HashMap::new()),
         }
     }
 
-    pub fn get_radius(&self) -> f32 {
-        self.radius
+    pub fn from_config(config: &GameConfig) -> Result<Self, SetupError> {
+        config.validate()?;
+        Ok(Board {
+            rings_per_player: config.rings_per_player,
+            run_length: config.run_length,
+            ..Board::with_radius(config.board_radius)
+        })
     }
 
-    // TODO: this is wrong!
-    pub fn board_coords(&self) -> Vec<HexCoord> {
-        let mut res = Vec::new();
-        for dy in HexCoord::new(0, 0)
-            .line_iter(&Direction::N)
-            .take_while(|c| self.valid_coord(c))
-            .chain(
-                HexCoord::new(0, -1)
-                    .line_iter(&Direction::S)
-                    .take_while(|c| self.valid_coord(c)),
-            )
-        {
-            for dx in dy
-                .line_iter(&Direction::SE)
-                .take_while(|c| self.valid_coord(c))
-                .chain(
-                    dy.line_iter(&Direction::NW)
-                        .skip(1)
-                        .take_while(|c| self.valid_coord(c)),
-                )
-            {
-                res.push(dx);
+    // The pieces of `board` under the rules of `config`, for boards restored by
+    // decode(), which only stores the fields.
+    pub fn with_config(board: &Board, config: &GameConfig) -> Result<Self, SetupError> {
+        let mut res = Board::from_config(config)?;
+        for (c, piece) in board.iter_pieces() {
+            if !res.is_on_board(&c) {
+                return Err(SetupError::PieceOffBoard(c));
             }
+            res.place_unchecked(&piece, &c);
         }
-        res
+        Ok(res)
+    }
+
+    // Rendering radius, derived from hex_radius() so the two can't disagree. It
+    // lies between the outermost fields and the missing corners.
+    pub fn get_radius(&self) -> f32 {
+        self.hex_radius as f32 - 0.3
+    }
+
+    // Markers in a row that score.
+    pub fn run_length(&self) -> usize {
+        self.run_length
+    }
+
+    // Integer radius for move generation and bounds checks.
+    pub fn hex_radius(&self) -> i32 {
+        self.hex_radius
+    }
+
+    pub fn board_coords(&self) -> Vec<HexCoord> {
+        let r = self.hex_radius as i8;
+        (-r..=r)
+            .flat_map(|x| (-r..=r).map(move |y| HexCoord::new(x, y)))
+            .filter(|c| self.is_on_board(c))
+            .collect()
     }
 
     pub fn closest_field_to_xy(&self, x: f32, y: f32) -> Option<(HexCoord, f32)> {
-        let closest = HexCoord::closest_coord_to_point(&Point(x, y));
-        if self.valid_coord(&closest.0) {
-            Some(closest)
-        } else {
-            None
-        }
+        nearest_coord(Point(x, y), f32::INFINITY, self.hex_radius)
     }
 
-    pub fn valid_coord(&self, coord: &HexCoord) -> bool {
-        coord.cartesian_sq_norm() <= num::pow(self.radius, 2)
+    // The field at (x, y), if one lies within `max_dist`.
+    pub fn closest_field_within(&self, x: f32, y: f32, max_dist: f32) -> Option<HexCoord> {
+        nearest_coord(Point(x, y), max_dist, self.hex_radius).map(|(c, _)| c)
+    }
+
+    // Whether `coord` is one of board_coords(), regardless of what is on it.
+    pub fn is_on_board(&self, coord: &HexCoord) -> bool {
+        coord.on_hex_board(self.hex_radius)
+    }
+
+    pub fn coord_from_label(&self, label: &str) -> Option<HexCoord> {
+        parse_label(label).filter(|c| self.is_on_board(c))
+    }
+
+    pub fn label_for(&self, c: &HexCoord) -> Option<String> {
+        self.is_on_board(c).then(|| coord_label(c))
     }
 
     pub fn occupied(&self, coord: &HexCoord) -> Option<&Piece> {
@@ -69,7 +142,7 @@ impl Board {
     }
 
     pub fn free_board_field(&self, coord: &HexCoord) -> bool {
-        self.valid_coord(coord) && self.occupied(coord).is_none()
+        self.is_on_board(coord) && self.occupied(coord).is_none()
     }
 
     pub fn ring_at(&self, coord: &HexCoord) -> Option<&Piece> {
@@ -81,12 +154,12 @@ impl Board {
     }
 
     pub fn player_ring_at(&self, coord: &HexCoord, player: &Player) -> bool {
-        self.ring_at(coord).map_or(false, |p| p.belongs_to(*player))
+        self.ring_at(coord).map_or(false, |p| p.owned_by(*player))
     }
 
     pub fn player_marker_at(&self, coord: &HexCoord, player: &Player) -> bool {
         self.marker_at(coord)
-            .map_or(false, |p| p.belongs_to(*player))
+            .map_or(false, |p| p.owned_by(*player))
     }
 
     fn filter_board<F>(&self, f: F) -> impl Iterator<Item = &HexCoord>
@@ -108,60 +181,139 @@ impl Board {
     }
 
     pub fn player_markers(&self, player: Player) -> impl Iterator<Item = &HexCoord> {
-        self.filter_board(move |k, v| v.is_marker() && v.belongs_to(player))
+        self.filter_board(move |k, v| v.is_marker() && v.owned_by(player))
     }
 
     pub fn player_rings(&self, player: Player) -> impl Iterator<Item = &HexCoord> {
-        self.filter_board(move |k, v| v.is_ring() && v.belongs_to(player))
+        self.filter_board(move |k, v| v.is_ring() && v.owned_by(player))
+    }
+
+    // Owned and sorted, for callers that change the board while going through them.
+    pub fn ring_coords(&self, player: Player) -> Vec<HexCoord> {
+        let mut coords: Vec<HexCoord> = self.player_rings(player).copied().collect();
+        coords.sort();
+        coords
+    }
+
+    pub fn marker_coords(&self, player: Player) -> Vec<HexCoord> {
+        let mut coords: Vec<HexCoord> = self.player_markers(player).copied().collect();
+        coords.sort();
+        coords
+    }
+
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (HexCoord, Piece)> + '_ {
+        self.board_map.iter().map(|(c, p)| (*c, *p))
+    }
+
+    pub fn total_markers(&self) -> usize {
+        self.markers().count()
+    }
+
+    // Flipping keeps a marker on the board, only removing a run returns markers.
+    pub fn markers_remaining(&self) -> usize {
+        MARKER_SUPPLY.saturating_sub(self.total_markers())
+    }
+
+    // (white rings, black rings, white markers, black markers)
+    pub fn census(&self) -> (usize, usize, usize, usize) {
+        self.iter_pieces()
+            .fold((0, 0, 0, 0), |(wr, br, wm, bm), (_, piece)| match piece {
+                Piece::Ring(Player::White) => (wr + 1, br, wm, bm),
+                Piece::Ring(Player::Black) => (wr, br + 1, wm, bm),
+                Piece::Marker(Player::White) => (wr, br, wm + 1, bm),
+                Piece::Marker(Player::Black) => (wr, br, wm, bm + 1),
+            })
+    }
+
+    // Checks that the board is a consistent starting position for `phase`.
+    pub fn validate_for_phase(&self, phase: Phase) -> Result<(), SetupError> {
+        if let Some(c) = self.board_map.keys().find(|c| !self.is_on_board(c)) {
+            return Err(SetupError::PieceOffBoard(*c));
+        }
+        let (white_rings, black_rings, white_markers, black_markers) = self.census();
+        if phase == Phase::PlaceRing && white_markers + black_markers > 0 {
+            return Err(SetupError::MarkersBeforeRings);
+        }
+        if white_rings > self.rings_per_player {
+            return Err(SetupError::TooManyRings(Player::White));
+        }
+        if black_rings > self.rings_per_player {
+            return Err(SetupError::TooManyRings(Player::Black));
+        }
+        Ok(())
     }
 
     pub fn belongs_to(&self, coord: &HexCoord) -> Option<Player> {
-        self.board_map.get(coord).map(|p| p.owner())
+        self.board_map.get(coord).map(|p| p.player())
     }
 
     pub fn remove(&mut self, coord: &HexCoord) -> Option<Piece> {
+        self.target_cache.get_mut().clear();
         self.board_map.remove(coord)
     }
 
     pub fn place_unchecked(&mut self, piece: &Piece, coord: &HexCoord) -> Option<Piece> {
+        self.target_cache.get_mut().clear();
         self.board_map.insert(*coord, *piece)
     }
 
-    pub fn place(&mut self, piece: &Piece, coord: &HexCoord) -> Option<Piece> {
-        assert!(self.free_board_field(coord));
-        self.place_unchecked(piece, coord)
+    // Checked alternative to place_unchecked().
+    pub fn place(&mut self, piece: &Piece, coord: &HexCoord) -> Result<(), PlaceError> {
+        if !self.is_on_board(coord) {
+            return Err(PlaceError::OffBoard(*coord));
+        }
+        if self.occupied(coord).is_some() {
+            return Err(PlaceError::Occupied(*coord));
+        }
+        if let Piece::Ring(player) = piece {
+            if self.player_rings(*player).count() >= self.rings_per_player {
+                return Err(PlaceError::TooManyRings(*player));
+            }
+        }
+        self.place_unchecked(piece, coord);
+        Ok(())
     }
 
-    fn ring_targets_in_dir(&self, from: &HexCoord, dir: &Direction) -> Vec<HexCoord> {
-        let mut iter = from.line_iter(dir).skip(1).peekable();
+    // Furthest field a ring at `from` can reach along `dir`: it slides over empty
+    // fields, may jump one contiguous line of markers and then has to stop on the
+    // first empty field behind them. Rings and the board boundary block.
+    pub fn ring_slide(&self, from: &HexCoord, dir: HexCoord) -> Option<HexCoord> {
+        let mut last_empty = None;
+        let mut current = *from + dir;
 
-        // take all empty fields along dir up to board boundary
-        let mut ret: Vec<HexCoord> = iter
-            .by_ref()
-            .peeking_take_while(|c| self.occupied(c).is_none() && self.valid_coord(c))
-            .collect();
+        while self.is_on_board(&current) && self.occupied(&current).is_none() {
+            last_empty = Some(current);
+            current = current + dir;
+        }
 
-        // return if the first non-empty field is a ring
-        if iter
-            .peeking_next(|c| self.ring_at(c).is_some() || !self.valid_coord(c))
-            .is_some()
-        {
-            return ret;
+        if self.marker_at(&current).is_none() {
+            return last_empty;
         }
 
-        // skip markers
-        let mut iter = iter.skip_while(|c| self.marker_at(c).is_some()).peekable();
+        while self.marker_at(&current).is_some() {
+            current = current + dir;
+        }
 
-        // if the next non-marker field is empty and within board boundaries, add to result list
-        if let Some(next) = iter.peeking_next(|c| self.occupied(c).is_none() && self.valid_coord(c))
-        {
-            ret.push(next);
+        if self.is_on_board(&current) && self.occupied(&current).is_none() {
+            Some(current)
+        } else {
+            last_empty
         }
+    }
 
-        ret
+    fn ring_targets_in_dir(&self, from: &HexCoord, dir: &Direction) -> Vec<HexCoord> {
+        match self.ring_slide(from, dir.dir_vec()) {
+            Some(to) => from
+                .range_iter(&to)
+                .unwrap()
+                .skip(1)
+                .filter(|c| self.occupied(c).is_none())
+                .collect(),
+            None => vec![],
+        }
     }
 
-    pub fn ring_targets(&self, from: &HexCoord) -> Vec<HexCoord> {
+    fn compute_ring_targets(&self, from: &HexCoord) -> Vec<HexCoord> {
         Direction::all()
             .into_iter()
             .map(|dir| self.ring_targets_in_dir(from, &dir))
@@ -169,6 +321,29 @@ impl Board {
             .collect()
     }
 
+    // Memoized until the next piece is placed or removed, so move generation,
+    // hover previews and the AI's evaluation share one computation per position.
+    pub fn ring_targets(&self, from: &HexCoord) -> Vec<HexCoord> {
+        self.target_cache
+            .borrow_mut()
+            .entry(*from)
+            .or_insert_with(|| self.compute_ring_targets(from))
+            .clone()
+    }
+
+    pub fn all_ring_targets(&self, player: Player) -> HashMap<HexCoord, Vec<HexCoord>> {
+        self.player_rings(player)
+            .map(|c| (*c, self.ring_targets(c)))
+            .collect()
+    }
+
+    // Number of fields the rings of `player` can move to.
+    pub fn ring_mobility(&self, player: Player) -> usize {
+        self.player_rings(player)
+            .map(|c| self.ring_targets(c).len())
+            .sum()
+    }
+
     fn marker_run_in_dir(
         &self,
         player: &Player,
@@ -196,6 +371,9 @@ impl Board {
             .collect()
     }
 
+    // Every removable run: a line longer than the run length holds one run per
+    // window, since the player chooses which markers of it to remove. Use
+    // maximal_runs() to count lines instead.
     pub fn runs(&self, player: &Player) -> Vec<Vec<HexCoord>> {
         let mut ret: Vec<Vec<HexCoord>> = vec![];
 
@@ -207,9 +385,52 @@ impl Board {
                 }
                 let res = self.marker_run_in_dir(player, mcoord, dir);
                 cache.extend(&res);
-                ret.extend(res.as_slice().windows(5).map(|x| x.to_vec()));
+                ret.extend(
+                    res.as_slice()
+                        .windows(self.run_length)
+                        .map(|x| x.to_vec()),
+                );
             }
         }
+        debug_assert!(ret.iter().all(|r| run_axis(r).is_some()));
+        ret
+    }
+
+    // Maximal lines of at least run length markers, each reported once.
+    pub fn maximal_runs(&self, player: &Player) -> Vec<Vec<HexCoord>> {
+        let mut ret: Vec<Vec<HexCoord>> = vec![];
+        for dir in [Direction::N, Direction::NE, Direction::SE].iter() {
+            let mut cache: HashSet<HexCoord> = HashSet::new();
+            for mcoord in self.player_markers(*player) {
+                if cache.contains(mcoord) {
+                    continue;
+                }
+                let res = self.marker_run_in_dir(player, mcoord, dir);
+                cache.extend(&res);
+                if res.len() >= self.run_length {
+                    ret.push(res);
+                }
+            }
+        }
+        ret
+    }
+
+    // The part of runs() that contains `c`, i.e. the runs that can appear or
+    // disappear when the field at `c` changes.
+    pub fn runs_through(&self, c: &HexCoord, player: &Player) -> Vec<Vec<HexCoord>> {
+        if !self.player_marker_at(c, player) {
+            return vec![];
+        }
+        let mut ret: Vec<Vec<HexCoord>> = vec![];
+        for dir in [Direction::N, Direction::NE, Direction::SE].iter() {
+            let res = self.marker_run_in_dir(player, c, dir);
+            ret.extend(
+                res.as_slice()
+                    .windows(self.run_length)
+                    .filter(|x| x.contains(c))
+                    .map(|x| x.to_vec()),
+            );
+        }
         ret
     }
 
@@ -241,27 +462,177 @@ impl Board {
         false
     }
 
+    // Fields strictly between two coords on a common line, empty if not on a line.
+    pub fn fields_between(&self, from: &HexCoord, to: &HexCoord) -> Vec<HexCoord> {
+        from.between_iter(to)
+            .map(|iter| iter.collect())
+            .unwrap_or_default()
+    }
+
+    // Markers a ring move from `start` to `end` would flip.
+    pub fn flip_count(&self, start: &HexCoord, end: &HexCoord) -> usize {
+        self.fields_between(start, end)
+            .iter()
+            .filter(|c| self.marker_at(c).is_some())
+            .count()
+    }
+
+    // A ring can't jump over rings, so a ring in between means the line isn't a
+    // legal ring path and nothing is flipped.
     pub fn flip_between(&mut self, start: &HexCoord, end: &HexCoord) -> Vec<HexCoord> {
-        let mut res = vec![];
-        if let Some(iter) = start.between_iter(end) {
-            iter.for_each(|c| {
-                if self.flip_marker(&c) {
-                    res.push(c);
-                }
-            });
+        let fields = self.fields_between(start, end);
+        if fields.iter().any(|c| self.ring_at(c).is_some()) {
+            return vec![];
         }
-        res
+        fields
+            .into_iter()
+            .filter(|c| self.flip_marker(c))
+            .collect()
     }
 
     pub fn clear(&mut self) {
+        self.target_cache.get_mut().clear();
         self.board_map.clear();
     }
+
+    pub fn hash(&self) -> u64 {
+        self.board_map
+            .iter()
+            .fold(0, |h, (c, p)| h ^ zobrist::piece_key(c, p))
+    }
+
+    // One byte for the radius, then a nibble per field in board_coords() order:
+    // 2 bits ring layer, 2 bits marker layer, each 0 = empty, 1 = white, 2 = black.
+    pub fn encode(&self) -> Vec<u8> {
+        let coords = self.board_coords();
+        let mut res = vec![0; 1 + coords.len().div_ceil(2)];
+        res[0] = self.hex_radius as u8;
+        for (i, c) in coords.iter().enumerate() {
+            let nibble = match self.occupied(c) {
+                None => 0,
+                Some(Piece::Ring(p)) => player_code(*p),
+                Some(Piece::Marker(p)) => player_code(*p) << 2,
+            };
+            res[1 + i / 2] |= nibble << (4 * (i % 2));
+        }
+        res
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Board, DecodeError> {
+        let (&radius, fields) = bytes.split_first().ok_or(DecodeError::Empty)?;
+        if radius == 0 || radius > i8::MAX as u8 / 2 {
+            return Err(DecodeError::InvalidRadius(radius));
+        }
+        let mut board = Board::with_radius(radius as i32);
+        let coords = board.board_coords();
+        let expected = coords.len().div_ceil(2);
+        if fields.len() != expected {
+            return Err(DecodeError::WrongLength {
+                expected,
+                found: fields.len(),
+            });
+        }
+        for (i, c) in coords.iter().enumerate() {
+            let nibble = (fields[i / 2] >> (4 * (i % 2))) & 0xf;
+            let piece = match (nibble & 0b11, nibble >> 2) {
+                (0, 0) => continue,
+                (ring, 0) => code_player(ring).map(Piece::Ring),
+                (0, marker) => code_player(marker).map(Piece::Marker),
+                _ => None,
+            };
+            let piece = piece.ok_or(DecodeError::InvalidField(*c))?;
+            board.place_unchecked(&piece, c);
+        }
+        Ok(board)
+    }
+}
+
+fn player_code(player: Player) -> u8 {
+    match player {
+        Player::White => 1,
+        Player::Black => 2,
+    }
+}
+
+fn code_player(code: u8) -> Option<Player> {
+    match code {
+        1 => Some(Player::White),
+        2 => Some(Player::Black),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use rand::prelude::*;
+
     use super::*;
 
+    #[test]
+    fn test_encode_round_trip() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for radius in [3, 5, 6] {
+            let coords = Board::with_radius(radius).board_coords();
+            for _ in 0..20 {
+                let mut board = Board::with_radius(radius);
+                for c in &coords {
+                    if !rng.gen_bool(0.4) {
+                        continue;
+                    }
+                    let player = if rng.gen() { Player::White } else { Player::Black };
+                    let piece = if rng.gen() {
+                        Piece::Ring(player)
+                    } else {
+                        Piece::Marker(player)
+                    };
+                    board.place_unchecked(&piece, c);
+                }
+
+                let bytes = board.encode();
+                assert_eq!(bytes.len(), 1 + coords.len().div_ceil(2));
+                let decoded = Board::decode(&bytes).unwrap();
+                assert_eq!(decoded.hash(), board.hash());
+                assert_eq!(decoded.board_coords(), coords);
+                assert_eq!(decoded.encode(), bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_is_canonical() {
+        let pieces = [
+            (HexCoord::new(0, 0), Piece::Ring(Player::White)),
+            (HexCoord::new(1, 2), Piece::Marker(Player::Black)),
+            (HexCoord::new(-3, -1), Piece::Ring(Player::Black)),
+        ];
+        let mut a = Board::new();
+        let mut b = Board::new();
+        for (c, p) in &pieces {
+            a.place_unchecked(p, c);
+        }
+        for (c, p) in pieces.iter().rev() {
+            b.place_unchecked(p, c);
+        }
+        b.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(2, 2));
+        b.remove(&HexCoord::new(2, 2));
+        assert_eq!(a.encode(), b.encode());
+
+        let mut bytes = a.encode();
+        bytes[1] = 0b1111;
+        assert_eq!(
+            Board::decode(&bytes).err(),
+            Some(DecodeError::InvalidField(Board::new().board_coords()[0]))
+        );
+        assert_eq!(
+            Board::decode(&bytes[..5]).err(),
+            Some(DecodeError::WrongLength {
+                expected: 43,
+                found: 4
+            })
+        );
+        assert_eq!(Board::decode(&[]).err(), Some(DecodeError::Empty));
+    }
+
     #[test]
     fn test_ring_target() {
         let mut board = Board::new();
@@ -278,7 +649,7 @@ mod test {
             vec![HexCoord::new(0, 0), HexCoord::new(3, 0)]
         );
         println!("{}", HexCoord::new(5, 4).cartesian_sq_norm());
-        println!("{}", board.radius * board.radius);
+        println!("{}", board.get_radius() * board.get_radius());
 
         // free until boundary
         assert_eq!(
@@ -317,6 +688,257 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_ring_slide() {
+        let mut board = Board::new();
+        let from = HexCoord::new(0, 0);
+        let n = Direction::N.dir_vec();
+
+        // over empty fields up to the boundary
+        assert_eq!(board.ring_slide(&from, n), Some(HexCoord::new(0, 4)));
+
+        // jumping a block of markers stops on the first empty field behind it
+        board.place_unchecked(&Piece::Marker(Player::White), &(0, 2).into());
+        board.place_unchecked(&Piece::Marker(Player::Black), &(0, 3).into());
+        assert_eq!(board.ring_slide(&from, n), Some(HexCoord::new(0, 4)));
+        assert_eq!(board.ring_targets_in_dir(&from, &Direction::N).len(), 2);
+
+        // markers directly in front of the ring
+        board.place_unchecked(&Piece::Marker(Player::White), &(0, 1).into());
+        assert_eq!(board.ring_slide(&from, n), Some(HexCoord::new(0, 4)));
+        assert_eq!(
+            board.ring_targets_in_dir(&from, &Direction::N),
+            vec![HexCoord::new(0, 4)]
+        );
+
+        // blocked by a ring
+        let se = Direction::SE.dir_vec();
+        board.place_unchecked(&Piece::Ring(Player::Black), &(1, 0).into());
+        assert_eq!(board.ring_slide(&from, se), None);
+        board.place_unchecked(&Piece::Ring(Player::Black), &(3, 0).into());
+        assert_eq!(board.ring_slide(&(1, 0).into(), se), Some(HexCoord::new(2, 0)));
+
+        // a ring behind the markers blocks the jump
+        board.place_unchecked(&Piece::Ring(Player::White), &(0, 4).into());
+        assert_eq!(board.ring_slide(&from, n), None);
+    }
+
+    #[test]
+    fn test_checked_place() {
+        let config = GameConfig {
+            rings_per_player: 2,
+            ..GameConfig::default()
+        };
+        let mut board = Board::from_config(&config).unwrap();
+        let white_ring = Piece::Ring(Player::White);
+
+        assert_eq!(board.place(&white_ring, &(0, 0).into()), Ok(()));
+        assert_eq!(board.ring_at(&(0, 0).into()), Some(&white_ring));
+
+        assert_eq!(
+            board.place(&Piece::Marker(Player::Black), &(0, 0).into()),
+            Err(PlaceError::Occupied(HexCoord::new(0, 0)))
+        );
+        assert_eq!(
+            board.place(&white_ring, &(0, 5).into()),
+            Err(PlaceError::OffBoard(HexCoord::new(0, 5)))
+        );
+        assert_eq!(board.place(&white_ring, &(1, 0).into()), Ok(()));
+        assert_eq!(
+            board.place(&white_ring, &(2, 0).into()),
+            Err(PlaceError::TooManyRings(Player::White))
+        );
+        assert_eq!(board.place(&Piece::Ring(Player::Black), &(2, 0).into()), Ok(()));
+        assert_eq!(board.place(&Piece::Marker(Player::White), &(3, 0).into()), Ok(()));
+    }
+
+    #[test]
+    fn test_ring_targets_cache() {
+        let mut board = Board::new();
+        let rings = [(0, 0), (2, 1), (-3, -2)];
+        for (x, y) in rings {
+            board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(x, y));
+        }
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 3));
+
+        let check = |board: &Board| {
+            let targets = board.all_ring_targets(Player::White);
+            assert_eq!(targets.len(), rings.len());
+            for (c, t) in &targets {
+                assert_eq!(*t, board.compute_ring_targets(c));
+            }
+            let fresh: usize = targets.keys().map(|c| board.compute_ring_targets(c).len()).sum();
+            assert_eq!(board.ring_mobility(Player::White), fresh);
+        };
+
+        // every change has to invalidate targets cached for the previous position
+        for blocker in [(1, 1), (0, -1), (-1, 0), (2, 3)] {
+            check(&board);
+            let blocker = HexCoord::new(blocker.0, blocker.1);
+            board.place_unchecked(&Piece::Marker(Player::Black), &blocker);
+        }
+        check(&board);
+        board.flip_marker(&HexCoord::new(1, 1));
+        check(&board);
+        board.remove(&HexCoord::new(0, -1));
+        check(&board);
+        board.remove(&HexCoord::new(0, 3));
+        check(&board);
+        assert!(!board
+            .all_ring_targets(Player::White)
+            .contains_key(&HexCoord::new(0, 3)));
+    }
+
+    #[test]
+    fn test_board_sizes() {
+        assert_eq!(Board::new().board_coords().len(), 85);
+        assert_eq!(Board::with_radius(4).board_coords().len(), 55);
+        assert_eq!(Board::with_radius(6).board_coords().len(), 121);
+
+        let board = Board::new();
+        assert!(board.board_coords().iter().all(|c| c.cartesian_sq_norm() <= 4.7 * 4.7));
+    }
+
+    #[test]
+    fn test_owned_coords_match_iterators() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(1, 0));
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-2, 1));
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 3));
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(2, 2));
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-1, -1));
+
+        for player in Player::all() {
+            let mut rings: Vec<HexCoord> = board.player_rings(player).copied().collect();
+            let mut markers: Vec<HexCoord> = board.player_markers(player).copied().collect();
+            rings.sort();
+            markers.sort();
+            assert_eq!(board.ring_coords(player), rings);
+            assert_eq!(board.marker_coords(player), markers);
+        }
+        assert_eq!(
+            board.ring_coords(Player::White),
+            vec![HexCoord::new(-2, 1), HexCoord::new(1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_is_on_board() {
+        let board = Board::new();
+        for c in [(0, 0), (4, 4), (-4, 0), (1, -3)] {
+            assert!(board.is_on_board(&HexCoord::from(c)), "{:?}", c);
+        }
+        // edges without the corners
+        for c in [(5, 1), (5, 4), (-1, -5), (1, -4), (-5, -3)] {
+            assert!(board.is_on_board(&HexCoord::from(c)), "{:?}", c);
+        }
+        for c in [(5, 0), (0, -5), (5, 5), (-5, -5), (6, 3), (3, -3), (-9, 0)] {
+            assert!(!board.is_on_board(&HexCoord::from(c)), "{:?}", c);
+        }
+
+        let coords = board.board_coords();
+        let r = board.hex_radius() as i8 + 1;
+        for x in -r..=r {
+            for y in -r..=r {
+                let c = HexCoord::new(x, y);
+                assert_eq!(board.is_on_board(&c), coords.contains(&c));
+            }
+        }
+        // occupancy doesn't matter
+        let mut board = board;
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 0));
+        assert!(board.is_on_board(&HexCoord::new(0, 0)));
+        assert!(!board.free_board_field(&HexCoord::new(0, 0)));
+    }
+
+    #[test]
+    fn test_hex_radius_matches_extent() {
+        for r in [3, 5, 6] {
+            let board = Board::with_radius(r);
+            let extent = board
+                .board_coords()
+                .iter()
+                .map(|c| {
+                    let (x, y) = (c.0 as i32, c.1 as i32);
+                    x.abs().max(y.abs()).max((x - y).abs())
+                })
+                .max();
+            assert_eq!(extent, Some(board.hex_radius()));
+            assert!(board.get_radius() < board.hex_radius() as f32);
+            assert!(board.get_radius() > (board.hex_radius() - 1) as f32);
+        }
+    }
+
+    #[test]
+    fn test_ring_targets_on_larger_board() {
+        let from = HexCoord::new(0, 0);
+        assert_eq!(Board::new().ring_targets_in_dir(&from, &Direction::N).len(), 4);
+
+        let board = Board::with_radius(6);
+        assert_eq!(
+            board.ring_targets_in_dir(&from, &Direction::N),
+            (1..=5).map(|y| HexCoord::new(0, y)).collect::<Vec<_>>()
+        );
+        assert_eq!(board.ring_targets(&from).len(), 30);
+        assert!(board.ring_targets(&from).iter().all(|c| board.is_on_board(c)));
+    }
+
+    #[test]
+    fn test_census() {
+        let mut board = Board::new();
+        assert_eq!(board.census(), (0, 0, 0, 0));
+
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 0));
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(1, 0));
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(2, 0));
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 1));
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 2));
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 3));
+        assert_eq!(board.census(), (2, 1, 1, 2));
+
+        board.remove(&HexCoord::new(1, 0));
+        board.remove(&HexCoord::new(0, 1));
+        board.flip_marker(&HexCoord::new(0, 2));
+        // replacing a marker by a ring
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 3));
+        assert_eq!(board.census(), (1, 2, 1, 0));
+
+        assert_eq!(board.iter_pieces().count(), 4);
+        assert!(board
+            .iter_pieces()
+            .all(|(c, p)| board.occupied(&c) == Some(&p)));
+    }
+
+    #[test]
+    fn test_validate_for_phase() {
+        let mut board = Board::new();
+        assert_eq!(board.validate_for_phase(Phase::PlaceRing), Ok(()));
+
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        assert_eq!(
+            board.validate_for_phase(Phase::PlaceRing),
+            Err(SetupError::MarkersBeforeRings)
+        );
+        assert_eq!(board.validate_for_phase(Phase::PlaceMarker), Ok(()));
+
+        board.clear();
+        for i in 0..6 {
+            board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(i - 3, 0));
+        }
+        assert_eq!(
+            board.validate_for_phase(Phase::PlaceMarker),
+            Err(SetupError::TooManyRings(Player::Black))
+        );
+
+        board.clear();
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(5, 0));
+        assert_eq!(
+            board.validate_for_phase(Phase::PlaceRing),
+            Err(SetupError::PieceOffBoard(HexCoord::new(5, 0)))
+        );
+    }
+
     #[test]
     fn flip_marker_test() {
         let mut board = Board::new();
@@ -333,7 +955,7 @@ mod test {
 
         assert!(board
             .marker_at(&c)
-            .map(|m| m.belongs_to(p.other()))
+            .map(|m| m.owned_by(p.other()))
             .unwrap())
     }
 
@@ -381,6 +1003,123 @@ mod test {
         assert_eq!(board.rings().count(), 0);
     }
 
+    #[test]
+    fn test_board_labels() {
+        let board = Board::new();
+        for (label, x, y) in [
+            ("a2", -5, -4),
+            ("a5", -5, -1),
+            ("k7", 5, 1),
+            ("k10", 5, 4),
+            ("f6", 0, 0),
+        ] {
+            let c = HexCoord::new(x, y);
+            assert_eq!(board.coord_from_label(label), Some(c));
+            assert_eq!(board.label_for(&c).as_deref(), Some(label));
+        }
+
+        assert_eq!(board.coord_from_label("a1"), None);
+        assert_eq!(board.coord_from_label("f12"), None);
+        assert_eq!(board.label_for(&HexCoord::new(-5, -5)), None);
+    }
+
+    #[test]
+    fn test_closest_field_within() {
+        let board = Board::new();
+        let c = HexCoord::new(1, 2);
+        let pt = Point::from(c);
+        assert_eq!(board.closest_field_within(pt.0, pt.1, 0.3), Some(c));
+        assert_eq!(board.closest_field_within(pt.0 + 0.29, pt.1, 0.3), Some(c));
+        assert_eq!(board.closest_field_within(pt.0 + 0.45, pt.1, 0.3), None);
+        assert_eq!(board.closest_field_within(20., 20., 0.3), None);
+    }
+
+    #[test]
+    fn test_fields_between() {
+        let board = Board::new();
+        assert_eq!(
+            board.fields_between(&HexCoord::new(0, -1), &HexCoord::new(0, 3)),
+            vec![HexCoord::new(0, 0), HexCoord::new(0, 1), HexCoord::new(0, 2)]
+        );
+        assert!(board
+            .fields_between(&HexCoord::new(0, 0), &HexCoord::new(0, 1))
+            .is_empty());
+        assert!(board
+            .fields_between(&HexCoord::new(0, 0), &HexCoord::new(1, 2))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_flip_between_refuses_rings() {
+        let mut board = Board::new();
+        for y in 0..3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, y));
+        }
+        let (from, to) = (HexCoord::new(0, -1), HexCoord::new(0, 4));
+        assert_eq!(board.flip_between(&from, &to).len(), 3);
+        assert_eq!(board.player_markers(Player::Black).count(), 3);
+
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 3));
+        let to = HexCoord::new(0, 5);
+        assert!(board.flip_between(&from, &to).is_empty());
+        assert_eq!(board.player_markers(Player::Black).count(), 3);
+        assert_eq!(
+            board.ring_at(&HexCoord::new(0, 3)),
+            Some(&Piece::Ring(Player::White))
+        );
+    }
+
+    #[test]
+    fn find_short_runs() {
+        let mut board = Board::from_config(&GameConfig {
+            run_length: 4,
+            ..GameConfig::default()
+        })
+        .unwrap();
+        for x in 0..3 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(x, 0));
+        }
+        assert!(board.runs(&Player::White).is_empty());
+
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
+        assert_eq!(
+            board.runs(&Player::White),
+            vec![(0..4).map(|x| HexCoord::new(x, 0)).collect::<Vec<_>>()]
+        );
+        assert_eq!(board.runs_through(&HexCoord::new(0, 0), &Player::White).len(), 1);
+
+        // a line of five holds two overlapping runs of four
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(4, 0));
+        assert_eq!(board.runs(&Player::White).len(), 2);
+        assert_eq!(board.runs_through(&HexCoord::new(2, 0), &Player::White).len(), 2);
+    }
+
+    #[test]
+    fn maximal_runs_count_lines_once() {
+        let mut board = Board::new();
+        let row = |y: i8, n: i8| (-2..-2 + n).map(move |x| HexCoord::new(x, y));
+        for c in row(0, 5) {
+            board.place_unchecked(&Piece::Marker(Player::White), &c);
+        }
+        assert_eq!(board.maximal_runs(&Player::White).len(), 1);
+        assert_eq!(board.runs(&Player::White).len(), 1);
+
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
+        assert_eq!(
+            board.maximal_runs(&Player::White),
+            vec![row(0, 6).collect::<Vec<_>>()]
+        );
+        // either five of the six can be removed
+        assert_eq!(board.runs(&Player::White).len(), 2);
+
+        let mut board = Board::new();
+        for c in row(0, 5).chain(row(-2, 5)) {
+            board.place_unchecked(&Piece::Marker(Player::Black), &c);
+        }
+        assert_eq!(board.maximal_runs(&Player::Black).len(), 2);
+        assert!(board.maximal_runs(&Player::White).is_empty());
+    }
+
     #[test]
     fn find_single_run() {
         let mut board = Board::new();
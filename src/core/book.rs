@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::core::actions::*;
+use crate::core::board::Board;
+use crate::core::command::Command;
+use crate::core::state::State;
+
+// Maps a position hash (State::hash) to the move to play in that position.
+#[derive(Clone, Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Action>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_table(table: Vec<(u64, Action)>) -> Self {
+        OpeningBook {
+            entries: table.into_iter().collect(),
+        }
+    }
+
+    // Replays each game from the empty board and records the first `max_plies` moves.
+    // If several games reach the same position, the first game wins.
+    pub fn from_games(games: &[Vec<Action>], max_plies: usize) -> Self {
+        let mut book = OpeningBook::new();
+        for game in games {
+            let mut state = State::new(Board::new());
+            for action in game.iter().take(max_plies) {
+                if !action.is_legal(&state) {
+                    break;
+                }
+                book.entries
+                    .entry(state.hash())
+                    .or_insert_with(|| action.clone());
+                action.execute(&mut state);
+            }
+        }
+        book
+    }
+
+    pub fn insert(&mut self, hash: u64, action: Action) {
+        self.entries.insert(hash, action);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Only returns moves that are legal in `state`, guarding against hash collisions.
+    pub fn lookup(&self, state: &State) -> Option<Action> {
+        self.entries
+            .get(&state.hash())
+            .filter(|a| a.is_legal(state))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::ai::RandomAI;
+    use crate::core::entities::Player;
+
+    use super::*;
+
+    #[test]
+    fn test_book_move_for_start_position() {
+        let mut state = State::new(Board::new());
+        let c = HexCoord::new(1, 2);
+        let book =
+            OpeningBook::from_table(vec![(state.hash(), Action::from(PlaceRing { coord: c }))]);
+
+        let mut ai = RandomAI::new(Player::White, 1).with_book(book);
+        assert_eq!(ai.book_move(&state).map(|a| a.coord()), Some(c));
+
+        ai.turn(&mut state);
+        assert_eq!(state.history.len(), 1);
+        assert!(state.board.player_ring_at(&c, &Player::White));
+    }
+
+    #[test]
+    fn test_book_miss_falls_through_to_search() {
+        let mut state = State::new(Board::new());
+        let book = OpeningBook::from_table(vec![(
+            state.hash() ^ 1,
+            Action::from(PlaceRing {
+                coord: HexCoord::new(1, 2),
+            }),
+        )]);
+
+        let mut ai = RandomAI::new(Player::White, 1).with_book(book);
+        assert!(ai.book_move(&state).is_none());
+
+        ai.turn(&mut state);
+        assert_eq!(state.history.len(), 1);
+        assert_eq!(state.board.rings().count(), 1);
+    }
+
+    #[test]
+    fn test_book_from_games() {
+        let game = vec![
+            Action::from(PlaceRing {
+                coord: HexCoord::new(0, 1),
+            }),
+            Action::from(PlaceRing {
+                coord: HexCoord::new(1, 0),
+            }),
+            Action::from(PlaceRing {
+                coord: HexCoord::new(2, 2),
+            }),
+        ];
+        let book = OpeningBook::from_games(&[game], 2);
+        assert_eq!(book.len(), 2);
+
+        let mut state = State::new(Board::new());
+        let first = book.lookup(&state).unwrap();
+        assert_eq!(first.coord(), HexCoord::new(0, 1));
+        first.execute(&mut state);
+        assert_eq!(book.lookup(&state).unwrap().coord(), HexCoord::new(1, 0));
+    }
+}
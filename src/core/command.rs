@@ -4,6 +4,8 @@ use crate::common::coord::HexCoord;
 use super::actions::*;
 use super::state::State;
 
+// undo() must exactly reverse execute() on the state execute() left behind;
+// State::make/unmake and the AI search rely on it.
 #[enum_dispatch]
 pub trait Command {
     fn is_legal(&self, state: &State) -> bool;
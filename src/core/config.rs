@@ -0,0 +1,66 @@
+use crate::core::board::{SetupError, RINGS_PER_PLAYER, RUN_LENGTH, STANDARD_RADIUS};
+use crate::core::entities::Player;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameConfig {
+    pub board_radius: i32,
+    pub rings_per_player: usize,
+    // markers in a row needed for a run
+    pub run_length: usize,
+    // Games still running after this many actions are decided by score.
+    pub max_plies: usize,
+    // places the first ring
+    pub first_player: Player,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            board_radius: STANDARD_RADIUS,
+            rings_per_player: RINGS_PER_PLAYER,
+            run_length: RUN_LENGTH,
+            max_plies: 500,
+            first_player: Player::White,
+        }
+    }
+}
+
+impl GameConfig {
+    // The longest line of a board misses the two corners at its ends, so it has
+    // 2 * radius - 1 fields.
+    pub fn validate(&self) -> Result<(), SetupError> {
+        let longest = (2 * self.board_radius - 1).max(0) as usize;
+        if self.run_length < 3 || self.run_length > longest {
+            return Err(SetupError::InvalidRunLength(self.run_length));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::board::Board;
+
+    #[test]
+    fn test_run_length_bounds() {
+        let config = |run_length| GameConfig {
+            run_length,
+            ..GameConfig::default()
+        };
+        assert_eq!(
+            Board::from_config(&config(0)).err(),
+            Some(SetupError::InvalidRunLength(0))
+        );
+        assert_eq!(config(2).validate(), Err(SetupError::InvalidRunLength(2)));
+        assert_eq!(config(3).validate(), Ok(()));
+        assert_eq!(config(9).validate(), Ok(()));
+        assert_eq!(config(10).validate(), Err(SetupError::InvalidRunLength(10)));
+
+        let small = GameConfig {
+            board_radius: 2,
+            ..config(4)
+        };
+        assert_eq!(small.validate(), Err(SetupError::InvalidRunLength(4)));
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum Player {
     Black,
@@ -5,6 +7,10 @@ pub enum Player {
 }
 
 impl Player {
+    pub fn all() -> [Player; 2] {
+        [Player::White, Player::Black]
+    }
+
     pub fn other(&self) -> Player {
         match *self {
             Player::Black => Player::White,
@@ -13,6 +19,15 @@ impl Player {
     }
 }
 
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::Black => write!(f, "Black"),
+            Player::White => write!(f, "White"),
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
 pub enum Piece {
     Ring(Player),
@@ -21,37 +36,65 @@ pub enum Piece {
 
 impl Piece {
     pub fn is_ring(&self) -> bool {
-        match *self {
-            Piece::Ring(_) => true,
-            _ => false,
-        }
+        matches!(self, Piece::Ring(_))
     }
 
     pub fn is_marker(&self) -> bool {
-        match *self {
-            Piece::Marker(_) => true,
-            _ => false,
-        }
+        matches!(self, Piece::Marker(_))
     }
 
-    pub fn belongs_to(&self, player: Player) -> bool {
-        match self {
-            Piece::Ring(p) => *p == player,
-            Piece::Marker(p) => *p == player,
+    pub fn player(&self) -> Player {
+        match *self {
+            Piece::Ring(p) | Piece::Marker(p) => p,
         }
     }
 
-    pub fn owner(&self) -> Player {
-        match self {
-            Piece::Ring(p) => p.clone(),
-            Piece::Marker(p) => p.clone(),
-        }
+    pub fn owned_by(&self, player: Player) -> bool {
+        self.player() == player
     }
 
     pub fn flip(&self) -> Option<Piece> {
         match *self {
-            Piece::Ring(p) => None,
+            Piece::Ring(_) => None,
             Piece::Marker(p) => Some(Piece::Marker(p.other())),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALL_PIECES: [Piece; 4] = [
+        Piece::Ring(Player::White),
+        Piece::Ring(Player::Black),
+        Piece::Marker(Player::White),
+        Piece::Marker(Player::Black),
+    ];
+
+    #[test]
+    fn test_is_ring_is_marker() {
+        for piece in ALL_PIECES {
+            let ring = matches!(piece, Piece::Ring(_));
+            assert_eq!(piece.is_ring(), ring);
+            assert_eq!(piece.is_marker(), !ring);
+        }
+    }
+
+    #[test]
+    fn test_player() {
+        assert_eq!(Piece::Ring(Player::White).player(), Player::White);
+        assert_eq!(Piece::Ring(Player::Black).player(), Player::Black);
+        assert_eq!(Piece::Marker(Player::White).player(), Player::White);
+        assert_eq!(Piece::Marker(Player::Black).player(), Player::Black);
+    }
+
+    #[test]
+    fn test_owned_by() {
+        for piece in ALL_PIECES {
+            let player = piece.player();
+            assert!(piece.owned_by(player));
+            assert!(!piece.owned_by(player.other()));
+        }
+    }
+}
@@ -1,16 +1,25 @@
+use std::fmt;
+
 use crate::common::coord::*;
+use crate::common::notation::{coord_label, parse_label};
 use crate::core::actions::*;
 use crate::core::command::*;
 use crate::core::ai::*;
 use crate::core::board::*;
+use crate::core::config::GameConfig;
 use crate::core::entities::*;
+use crate::core::ponder::Ponder;
 use crate::core::state::*;
-use crate::frontend::frontend::UiStatus;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiAction {
     ActionAtCoord(HexCoord),
+    RemoveRunAt(Vec<HexCoord>),
+    Resign,
+    OfferDraw,
+    AcceptDraw,
     Undo,
+    CancelMove,
     RequestUpdate,
     UiUpdated,
     NoAction,
@@ -24,64 +33,1186 @@ pub trait View {
     fn invalid_action(&self);
     fn request_update(&mut self);
     fn set_interactive(&mut self, flag: bool);
+    // The side played at this view, None if it is used for both (hot seat).
+    fn set_human_player(&mut self, player: Option<Player>);
     fn tick(&mut self, state: &State) -> UiAction;
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    HumanVsAi,
+    // both players click on the same view
+    HumanVsHuman,
+}
+
+// Everything needed to resume a game except the view and the AI, which are
+// supplied again on load. Moves are kept as actions and replayed on load.
+#[derive(Clone)]
+pub struct SessionBlob {
+    pub config: GameConfig,
+    pub mode: GameMode,
+    pub human_player: Player,
+    pub initial_board: Board,
+    pub history: Vec<Action>,
+    // differs from the replayed phase after a resignation or agreed draw
+    pub phase: Phase,
+    pub result: GameResult,
+    pub draw_offer: Option<Player>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    Setup(SetupError),
+    Board(DecodeError),
+    // a line of the text form that could not be parsed
+    Malformed(String),
+    Missing(&'static str),
+    // index of the first history entry that is not legal when replayed
+    IllegalAction(usize),
+    PhaseMismatch { saved: Phase, replayed: Phase },
+}
+
+impl From<SetupError> for LoadError {
+    fn from(e: SetupError) -> Self {
+        LoadError::Setup(e)
+    }
+}
+
+impl From<DecodeError> for LoadError {
+    fn from(e: DecodeError) -> Self {
+        LoadError::Board(e)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Setup(e) => write!(f, "invalid setup: {:?}", e),
+            LoadError::Board(e) => write!(f, "invalid board: {:?}", e),
+            LoadError::Malformed(line) => write!(f, "malformed entry: {}", line),
+            LoadError::Missing(key) => write!(f, "missing entry: {}", key),
+            LoadError::IllegalAction(idx) => write!(f, "move {} is not legal", idx + 1),
+            LoadError::PhaseMismatch { saved, replayed } => {
+                write!(f, "saved phase {} but replay ends in {}", saved, replayed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// Text form of a session, one "key value..." entry per line:
+//
+//   config 5 5 5 500 white
+//   mode human-vs-ai
+//   human white
+//   board 05000000...
+//   moves ring:f6 ring:c3 marker:f6 move:white:f6-f9 run:0:c3:a1,b2,c3,d4,e5 remove:white:f6
+//   phase move-ring f6
+//   result white resignation
+//   draw_offer none
+//
+// The board is the hex of Board::encode, the config lists radius, rings per
+// player, run length, ply cap and first player.
+impl SessionBlob {
+    pub fn encode(&self) -> String {
+        let c = &self.config;
+        let board: String = self
+            .initial_board
+            .encode()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let moves: Vec<String> = self.history.iter().map(encode_action).collect();
+        let phase = match self.phase {
+            Phase::PlaceRing => "place-ring".to_owned(),
+            Phase::PlaceMarker => "place-marker".to_owned(),
+            Phase::MoveRing(from) => format!("move-ring {}", coord_label(&from)),
+            Phase::RemoveRun => "remove-run".to_owned(),
+            Phase::RemoveRing => "remove-ring".to_owned(),
+            Phase::PlayerWon(p) => format!("won {}", player_name(p)),
+            Phase::Draw => "draw".to_owned(),
+        };
+        let result = match self.result {
+            GameResult::White(r) => format!("white {}", win_reason_name(r)),
+            GameResult::Black(r) => format!("black {}", win_reason_name(r)),
+            GameResult::Draw(r) => format!("draw {}", draw_reason_name(r)),
+            GameResult::Ongoing => "ongoing".to_owned(),
+        };
+        let mode = match self.mode {
+            GameMode::HumanVsAi => "human-vs-ai",
+            GameMode::HumanVsHuman => "human-vs-human",
+        };
+        [
+            format!(
+                "config {} {} {} {} {}",
+                c.board_radius,
+                c.rings_per_player,
+                c.run_length,
+                c.max_plies,
+                player_name(c.first_player)
+            ),
+            format!("mode {}", mode),
+            format!("human {}", player_name(self.human_player)),
+            format!("board {}", board),
+            format!("moves {}", moves.join(" ")).trim_end().to_owned(),
+            format!("phase {}", phase),
+            format!("result {}", result),
+            format!("draw_offer {}", self.draw_offer.map_or("none", player_name)),
+        ]
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect()
+    }
+
+    // Only parses the text; whether the moves can be replayed is checked by
+    // Game::load.
+    pub fn decode(s: &str) -> Result<SessionBlob, LoadError> {
+        let mut config = None;
+        let mut mode = None;
+        let mut human_player = None;
+        let mut initial_board = None;
+        let mut history = None;
+        let mut phase = None;
+        let mut result = None;
+        let mut draw_offer = None;
+
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let malformed = || LoadError::Malformed(line.to_owned());
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["config", radius, rings, run, plies, first] => {
+                    config = Some(GameConfig {
+                        board_radius: radius.parse().map_err(|_| malformed())?,
+                        rings_per_player: rings.parse().map_err(|_| malformed())?,
+                        run_length: run.parse().map_err(|_| malformed())?,
+                        max_plies: plies.parse().map_err(|_| malformed())?,
+                        first_player: parse_player(first).ok_or_else(malformed)?,
+                    })
+                }
+                ["mode", "human-vs-ai"] => mode = Some(GameMode::HumanVsAi),
+                ["mode", "human-vs-human"] => mode = Some(GameMode::HumanVsHuman),
+                ["human", p] => human_player = Some(parse_player(p).ok_or_else(malformed)?),
+                ["board", hex] => {
+                    let bytes = (0..hex.len())
+                        .step_by(2)
+                        .map(|i| {
+                            hex.get(i..i + 2)
+                                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                        })
+                        .collect::<Option<Vec<u8>>>()
+                        .ok_or_else(malformed)?;
+                    initial_board = Some(Board::decode(&bytes)?);
+                }
+                ["moves", moves @ ..] => {
+                    let actions: Option<Vec<Action>> =
+                        moves.iter().map(|m| decode_action(m)).collect();
+                    history = Some(actions.ok_or_else(malformed)?);
+                }
+                ["phase", rest @ ..] => {
+                    phase = Some(match rest {
+                        ["place-ring"] => Phase::PlaceRing,
+                        ["place-marker"] => Phase::PlaceMarker,
+                        ["move-ring", from] => {
+                            Phase::MoveRing(parse_label(from).ok_or_else(malformed)?)
+                        }
+                        ["remove-run"] => Phase::RemoveRun,
+                        ["remove-ring"] => Phase::RemoveRing,
+                        ["won", p] => Phase::PlayerWon(parse_player(p).ok_or_else(malformed)?),
+                        ["draw"] => Phase::Draw,
+                        _ => return Err(malformed()),
+                    })
+                }
+                ["result", rest @ ..] => {
+                    result = Some(match rest {
+                        ["ongoing"] => GameResult::Ongoing,
+                        ["draw", r] => {
+                            GameResult::Draw(parse_draw_reason(r).ok_or_else(malformed)?)
+                        }
+                        [p, r] => GameResult::win(
+                            parse_player(p).ok_or_else(malformed)?,
+                            parse_win_reason(r).ok_or_else(malformed)?,
+                        ),
+                        _ => return Err(malformed()),
+                    })
+                }
+                ["draw_offer", "none"] => draw_offer = Some(None),
+                ["draw_offer", p] => {
+                    draw_offer = Some(Some(parse_player(p).ok_or_else(malformed)?))
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        let config = config.ok_or(LoadError::Missing("config"))?;
+        let initial_board = initial_board.ok_or(LoadError::Missing("board"))?;
+        Ok(SessionBlob {
+            config,
+            mode: mode.ok_or(LoadError::Missing("mode"))?,
+            human_player: human_player.ok_or(LoadError::Missing("human"))?,
+            // the encoded board only has the fields, the rules come from the config
+            initial_board: Board::with_config(&initial_board, &config)?,
+            history: history.ok_or(LoadError::Missing("moves"))?,
+            phase: phase.ok_or(LoadError::Missing("phase"))?,
+            result: result.ok_or(LoadError::Missing("result"))?,
+            draw_offer: draw_offer.ok_or(LoadError::Missing("draw_offer"))?,
+        })
+    }
+}
+
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::White => "white",
+        Player::Black => "black",
+    }
+}
+
+fn parse_player(s: &str) -> Option<Player> {
+    match s {
+        "white" => Some(Player::White),
+        "black" => Some(Player::Black),
+        _ => None,
+    }
+}
+
+fn win_reason_name(reason: WinReason) -> &'static str {
+    match reason {
+        WinReason::RingsCaptured => "rings-captured",
+        WinReason::Resignation => "resignation",
+        WinReason::Timeout => "timeout",
+        WinReason::Stalemate => "stalemate",
+    }
+}
+
+fn parse_win_reason(s: &str) -> Option<WinReason> {
+    [
+        WinReason::RingsCaptured,
+        WinReason::Resignation,
+        WinReason::Timeout,
+        WinReason::Stalemate,
+    ]
+    .into_iter()
+    .find(|r| win_reason_name(*r) == s)
+}
+
+fn draw_reason_name(reason: DrawReason) -> &'static str {
+    match reason {
+        DrawReason::Agreement => "agreement",
+        DrawReason::Repetition => "repetition",
+        DrawReason::Unknown => "unknown",
+    }
+}
+
+fn parse_draw_reason(s: &str) -> Option<DrawReason> {
+    [DrawReason::Agreement, DrawReason::Repetition, DrawReason::Unknown]
+        .into_iter()
+        .find(|r| draw_reason_name(*r) == s)
+}
+
+fn encode_action(action: &Action) -> String {
+    let labels = |coords: &[HexCoord]| coords.iter().map(coord_label).collect::<Vec<_>>().join(",");
+    match action {
+        Action::PlaceRing(a) => format!("ring:{}", coord_label(&a.coord)),
+        Action::PlaceMarker(a) => format!("marker:{}", coord_label(&a.coord)),
+        Action::MoveRing(a) => format!(
+            "move:{}:{}-{}",
+            player_name(a.player),
+            coord_label(&a.from),
+            coord_label(&a.to)
+        ),
+        Action::RemoveRun(a) => format!(
+            "run:{}:{}:{}",
+            a.run_idx,
+            coord_label(&a.coord),
+            labels(&a.run)
+        ),
+        Action::RemoveRing(a) => {
+            format!("remove:{}:{}", player_name(a.player), coord_label(&a.coord))
+        }
+    }
+}
+
+fn decode_action(s: &str) -> Option<Action> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let action = match parts.as_slice() {
+        ["ring", c] => Action::from(PlaceRing {
+            coord: parse_label(c)?,
+        }),
+        ["marker", c] => Action::from(PlaceMarker {
+            coord: parse_label(c)?,
+        }),
+        ["move", p, from_to] => {
+            let (from, to) = from_to.split_once('-')?;
+            Action::from(MoveRing {
+                from: parse_label(from)?,
+                to: parse_label(to)?,
+                player: parse_player(p)?,
+            })
+        }
+        ["run", idx, c, run] => Action::from(RemoveRun {
+            run_idx: idx.parse().ok()?,
+            run: run.split(',').map(parse_label).collect::<Option<_>>()?,
+            coord: parse_label(c)?,
+        }),
+        ["remove", p, c] => Action::from(RemoveRing {
+            coord: parse_label(c)?,
+            player: parse_player(p)?,
+        }),
+        _ => return None,
+    };
+    Some(action)
+}
+
+pub type MoveCallback = Box<dyn FnMut(&State, &[StateChange])>;
+pub type GameOverCallback = Box<dyn FnMut(&State)>;
+
 pub struct Game {
     state: State,
     view: Box<dyn View>,
     human_player: Player,
-    ai: RandomAI,
+    mode: GameMode,
+    config: GameConfig,
+    ai: Box<dyn Ai>,
+    // player with a pending draw offer, withdrawn when the opponent moves instead
+    draw_offer: Option<Player>,
+    on_move: Option<MoveCallback>,
+    on_game_over: Option<GameOverCallback>,
+    ponder: Option<Ponder>,
 }
 
 impl Game {
-    pub fn new(human_player: Player, view: Box<dyn View>, board: Board) -> Self {
+    pub fn new(
+        human_player: Player,
+        view: Box<dyn View>,
+        board: Board,
+    ) -> Result<Self, SetupError> {
+        board.validate_for_phase(Phase::PlaceRing)?;
+        let state = State::new(board);
         let mut game = Game {
-            state: State::new(board),
+            state,
             view,
             human_player,
-            ai: RandomAI::new(human_player.other(), 3),
+            mode: GameMode::HumanVsAi,
+            config: GameConfig::default(),
+            ai: Box::new(RandomAI::new(human_player.other(), 3).with_symmetry_dedup(true)),
+            draw_offer: None,
+            on_move: None,
+            on_game_over: None,
+            ponder: None,
         };
+        game.sync_view_player();
         game.view.request_update();
-        game
+        Ok(game)
     }
 
-    pub fn execute_for_coord(&mut self, coord: &HexCoord) -> bool {
-        if let Some(some_move) = self.state.legal_moves().into_iter().find(|m| m.coord() == *coord) {
-            if !some_move.is_legal(&self.state) {
-                return false;
+    fn sync_view_player(&mut self) {
+        let human = match self.mode {
+            GameMode::HumanVsAi => Some(self.human_player),
+            GameMode::HumanVsHuman => None,
+        };
+        self.view.set_human_player(human);
+    }
+
+    // Lets the AI ponder for `budget` search steps per idle tick of the human.
+    pub fn with_pondering(mut self, budget: usize) -> Self {
+        self.ponder = Some(Ponder::new(budget));
+        self
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // The AI's evaluation after each ply of the history, for plotting how the
+    // game swung. Replays from the initial board one action at a time.
+    pub fn analyze(&self, ai: &dyn Ai) -> Vec<i32> {
+        let mut state = self.state.replay_to(0);
+        self.state
+            .history
+            .iter()
+            .map(|action| {
+                action.execute(&mut state);
+                ai.evaluate(&state)
+            })
+            .collect()
+    }
+
+    pub fn save(&self) -> SessionBlob {
+        SessionBlob {
+            config: self.config,
+            mode: self.mode,
+            human_player: self.human_player,
+            initial_board: self.state.initial_board.clone(),
+            history: self.state.history.clone(),
+            phase: self.state.current_phase,
+            result: self.state.result(),
+            draw_offer: self.draw_offer,
+        }
+    }
+
+    // Replays the saved history with legality checks, so a blob that does not
+    // describe a reachable position is refused. The AI starts fresh.
+    pub fn load(blob: SessionBlob, view: Box<dyn View>) -> Result<Game, LoadError> {
+        blob.config.validate()?;
+        let board = Board::with_config(&blob.initial_board, &blob.config)?;
+        let mut game = Game::new(blob.human_player, view, board)?;
+        game.config = blob.config;
+        game.mode = blob.mode;
+        game.sync_view_player();
+        game.state.first_player = blob.config.first_player;
+        game.state.current_player = blob.config.first_player;
+        for (idx, action) in blob.history.iter().enumerate() {
+            game.state
+                .apply_action(action)
+                .map_err(|_| LoadError::IllegalAction(idx))?;
+        }
+        let replayed = game.state.current_phase;
+        if replayed != blob.phase {
+            if !matches!(blob.phase, Phase::PlayerWon(_) | Phase::Draw) {
+                return Err(LoadError::PhaseMismatch {
+                    saved: blob.phase,
+                    replayed,
+                });
             }
-            some_move.execute(&mut self.state);
-            return true;
+            game.state.set_phase(blob.phase);
+            game.state.end(blob.result);
+        }
+        game.draw_offer = blob.draw_offer;
+        Ok(game)
+    }
+
+    // Called after every action that changed the position, by either player.
+    pub fn set_on_move(&mut self, callback: impl FnMut(&State, &[StateChange]) + 'static) {
+        self.on_move = Some(Box::new(callback));
+    }
+
+    // Called once when the game reaches PlayerWon or Draw.
+    pub fn set_on_game_over(&mut self, callback: impl FnMut(&State) + 'static) {
+        self.on_game_over = Some(Box::new(callback));
+    }
+
+    fn notify_move(&mut self) {
+        if let Some(callback) = self.on_move.as_mut() {
+            callback(&self.state, &self.state.last_state_change);
+        }
+    }
+
+    pub fn execute_for_coord(&mut self, coord: &HexCoord) -> Result<(), GameError> {
+        self.state.execute_for_coord(coord)
+    }
+
+    fn is_over(&self) -> bool {
+        matches!(
+            self.state.current_phase,
+            Phase::PlayerWon(_) | Phase::Draw
+        )
+    }
+
+    pub fn resign(&mut self, player: Player) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        self.state
+            .end(GameResult::win(player.other(), WinReason::Resignation));
+        true
+    }
+
+    pub fn offer_draw(&mut self, player: Player) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        self.draw_offer = Some(player);
+        true
+    }
+
+    pub fn accept_draw(&mut self, player: Player) -> bool {
+        if self.is_over() || self.draw_offer != Some(player.other()) {
+            return false;
+        }
+        self.draw_offer = None;
+        self.state.end(GameResult::Draw(DrawReason::Agreement));
+        true
+    }
+
+    // Player the view speaks for; in a hotseat game whoever is to move.
+    fn acting_player(&self) -> Player {
+        match self.mode {
+            GameMode::HumanVsAi => self.human_player,
+            GameMode::HumanVsHuman => self.state.current_player,
+        }
+    }
+
+    // The AI only takes a draw when it is behind on points.
+    fn ai_accepts_draw(&self) -> bool {
+        self.state.leader() == Some(self.human_player)
+    }
+
+    // Runs can share their first marker, so they are selected by their full extent.
+    pub fn execute_remove_run(&mut self, run: &[HexCoord]) -> bool {
+        let action = self.state.legal_moves().into_iter().find(|m| match m {
+            Action::RemoveRun(a) => a.run == run,
+            _ => false,
+        });
+        match action {
+            Some(action) if action.is_legal(&self.state) => {
+                action.execute(&mut self.state);
+                true
+            }
+            _ => false,
         }
-        false
     }
 
     // TOD: State update missing after White player move. Ai kicks in an blocks animation/update ...
     pub fn tick(&mut self) {
+        if self.state.recover_empty_remove_run() {
+            self.view.request_update();
+        }
         let ui_action = self.view.tick(&mut self.state);
         if ui_action == UiAction::Busy {
             return;
         }
-        if self.state.current_player == self.human_player.other() {
-            self.view.request_update();
-            println!("START AI");
-            self.ai.turn(&mut self.state);
+        let was_over = self.is_over();
+        self.handle(ui_action);
+        if !was_over && self.is_over() {
+            if let Some(callback) = self.on_game_over.as_mut() {
+                callback(&self.state);
+            }
+        }
+    }
+
+    fn handle(&mut self, ui_action: UiAction) {
+        if self.mode == GameMode::HumanVsAi
+            && self.state.current_player == self.human_player.other()
+        {
+            if self.draw_offer == Some(self.human_player) {
+                if self.ai_accepts_draw() {
+                    self.accept_draw(self.human_player.other());
+                    self.view.request_update();
+                    return;
+                }
+                self.draw_offer = None;
+            }
+            // one step per tick keeps the view rendering while the AI thinks
+            let plies = self.state.history.len();
+            let status = match self.ponder.as_mut().and_then(|p| p.take(&self.state)) {
+                Some(action) => {
+                    action.execute(&mut self.state);
+                    AiStatus::Done
+                }
+                None => self.ai.step(&mut self.state),
+            };
+            if status == AiStatus::Thinking {
+                return;
+            }
+            if self.state.history.len() != plies {
+                self.notify_move();
+            }
             self.view.request_update();
-            println!("END AI");
             return;
         }
 
+        if ui_action == UiAction::NoAction && !self.is_over() {
+            if let Some(ponder) = self.ponder.as_mut() {
+                ponder.step(&self.state, self.ai.as_mut());
+            }
+            return;
+        }
+
+        let is_move = matches!(
+            ui_action,
+            UiAction::ActionAtCoord(_)
+                | UiAction::RemoveRunAt(_)
+                | UiAction::Undo
+                | UiAction::CancelMove
+        );
+        let mover = self.acting_player();
         let successful_action = match ui_action {
-            UiAction::ActionAtCoord(coord) => self.execute_for_coord(&coord),
-            UiAction::Undo => { println!("Received undo!"); self.state.undo() },
+            UiAction::ActionAtCoord(coord) => self.execute_for_coord(&coord).is_ok(),
+            UiAction::RemoveRunAt(run) => self.execute_remove_run(&run),
+            UiAction::Undo => self.state.undo_turn().is_ok(),
+            UiAction::CancelMove => self.state.cancel_move_ring().is_ok(),
+            UiAction::Resign => self.resign(self.acting_player()),
+            UiAction::OfferDraw => self.offer_draw(self.acting_player()),
+            UiAction::AcceptDraw => self.accept_draw(self.acting_player()),
             _ => false,
         };
 
         if successful_action {
+            if is_move {
+                // an offer only stands until the opponent's next move
+                if self.draw_offer == Some(mover.other()) {
+                    self.draw_offer = None;
+                }
+                self.notify_move();
+            }
             println!("UPDATED REQUESTED AFTER SUCCESSFUL ACTION");
             self.view.request_update();
         }
     }
+}
+
+// Assembles a Game from an empty board of the configured size; anything not set
+// gets the defaults of the desktop app. The view is built by the caller, so core
+// doesn't depend on a frontend.
+pub struct GameBuilder {
+    human: Player,
+    mode: GameMode,
+    config: GameConfig,
+    ai: Option<Box<dyn Ai>>,
+    view: Box<dyn View>,
+}
+
+impl GameBuilder {
+    pub fn new(view: Box<dyn View>) -> Self {
+        GameBuilder {
+            human: Player::White,
+            mode: GameMode::default(),
+            config: GameConfig::default(),
+            ai: None,
+            view,
+        }
+    }
+
+    pub fn human(mut self, player: Player) -> Self {
+        self.human = player;
+        self
+    }
+
+    pub fn mode(mut self, mode: GameMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn config(mut self, config: GameConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn ai(mut self, ai: Box<dyn Ai>) -> Self {
+        self.ai = Some(ai);
+        self
+    }
+
+    pub fn build(self) -> Result<Game, SetupError> {
+        let board = Board::from_config(&self.config)?;
+        let mut game = Game::new(self.human, self.view, board)?;
+        game.state = State::from_config(&self.config)?;
+        game.mode = self.mode;
+        game.sync_view_player();
+        game.config = self.config;
+        if let Some(mut ai) = self.ai {
+            ai.set_player(self.human.other());
+            game.ai = ai;
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::*;
+
+    use crate::core::minimax::{EvalWeights, MinimaxAI};
+    use crate::frontend::headless::HeadlessView;
+
+    use super::*;
+
+    #[test]
+    fn test_scripted_human_turn_with_run() {
+        let script = vec![
+            UiAction::ActionAtCoord(HexCoord::new(2, 0)),
+            UiAction::ActionAtCoord(HexCoord::new(2, 1)),
+            UiAction::ActionAtCoord(HexCoord::new(-2, 0)),
+            UiAction::ActionAtCoord(HexCoord::new(2, 1)),
+        ];
+        let view = HeadlessView::new(script);
+        let update_requests = view.update_requests();
+        let mut game = Game::new(Player::White, Box::new(view), Board::new()).unwrap();
+
+        for i in -2..=1 {
+            game.state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        game.state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 0));
+        game.state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-3, 2));
+        game.state
+            .board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        game.state.set_phase(Phase::PlaceMarker);
+
+        for _ in 0..4 {
+            game.tick();
+        }
+        assert_eq!(game.state().points_white, 1);
+        assert_eq!(game.state().current_player, Player::Black);
+        assert_eq!(game.state().current_phase, Phase::PlaceMarker);
+
+        // the AI answers without further input
+        game.tick();
+        assert_eq!(game.state().current_player, Player::Black);
+        assert!(matches!(game.state().current_phase, Phase::MoveRing(_)));
+
+        assert_eq!(game.state().points_white, 1);
+        assert_eq!(game.state().points_black, 0);
+        assert!(update_requests.get() >= 5);
+    }
+
+    #[test]
+    fn test_remove_run_sharing_first_marker() {
+        let origin = HexCoord::new(0, 0);
+        let north: Vec<HexCoord> = (0..5).map(|y| HexCoord::new(0, y)).collect();
+        let south_east: Vec<HexCoord> = (0..5).map(|x| HexCoord::new(x, 0)).collect();
+
+        for (removed, kept) in [(&north, &south_east), (&south_east, &north)] {
+            let view = HeadlessView::new(vec![UiAction::RemoveRunAt(removed.clone())]);
+            let mut game = Game::new(Player::White, Box::new(view), Board::new()).unwrap();
+            for c in north.iter().chain(south_east.iter()) {
+                game.state
+                    .board
+                    .place_unchecked(&Piece::Marker(Player::White), c);
+            }
+            game.state
+                .board
+                .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(-3, 2));
+            game.state.compute_runs();
+            game.state.set_phase(Phase::RemoveRun);
+            assert!(game.state().runs_white.iter().all(|r| r[0] == origin));
+
+            game.tick();
+            assert_eq!(game.state().current_phase, Phase::RemoveRing);
+            assert!(removed.iter().all(|c| game.state().board.occupied(c).is_none()));
+            assert!(kept[1..]
+                .iter()
+                .all(|c| game.state().board.player_marker_at(c, &Player::White)));
+        }
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .build()
+            .unwrap();
+        assert_eq!(game.state().current_phase, Phase::PlaceRing);
+        assert_eq!(game.state().board.iter_pieces().count(), 0);
+        assert_eq!(game.mode, GameMode::HumanVsAi);
+        assert_eq!(game.human_player, Player::White);
+    }
+
+    #[test]
+    fn test_hotseat_has_no_ai() {
+        let script = vec![
+            UiAction::ActionAtCoord(HexCoord::new(0, 0)),
+            UiAction::ActionAtCoord(HexCoord::new(1, 1)),
+        ];
+        let view = HeadlessView::new(script);
+        let view_player = view.human_player();
+        let mut game = GameBuilder::new(Box::new(view))
+            .mode(GameMode::HumanVsHuman)
+            .config(GameConfig {
+                board_radius: 4,
+                ..GameConfig::default()
+            })
+            .build()
+            .unwrap();
+        // the view plays both sides
+        assert_eq!(view_player.get(), None);
+        game.tick();
+        game.tick();
+        let board = &game.state().board;
+        assert_eq!(board.hex_radius(), 4);
+        assert_eq!(board.player_rings(Player::White).count(), 1);
+        assert_eq!(board.player_rings(Player::Black).count(), 1);
+        assert_eq!(board.ring_at(&HexCoord::new(1, 1)), Some(&Piece::Ring(Player::Black)));
+    }
+
+    #[test]
+    fn test_ai_opens_as_white() {
+        let view = HeadlessView::new(vec![]);
+        let view_player = view.human_player();
+        let mut game = GameBuilder::new(Box::new(view))
+            .human(Player::Black)
+            .build()
+            .unwrap();
+        assert_eq!(view_player.get(), Some(Player::Black));
+        assert_eq!(game.state().current_player, Player::White);
+        game.tick();
+        assert_eq!(game.state().history.len(), 1);
+        assert_eq!(game.state().board.player_rings(Player::White).count(), 1);
+        assert_eq!(game.state().current_player, Player::Black);
+    }
+
+    // Thinks for a number of steps, then plays the first legal move.
+    struct SlowAI {
+        steps: usize,
+    }
+
+    impl Ai for SlowAI {
+        fn turn(&mut self, state: &mut State) {
+            if let Some(action) = state.legal_moves().into_iter().next() {
+                action.execute(state);
+            }
+        }
+
+        fn step(&mut self, state: &mut State) -> AiStatus {
+            if self.steps > 0 {
+                self.steps -= 1;
+                return AiStatus::Thinking;
+            }
+            self.turn(state);
+            AiStatus::Done
+        }
+    }
+
+    #[test]
+    fn test_slow_ai_moves_across_ticks() {
+        let view = HeadlessView::new(vec![]);
+        let update_requests = view.update_requests();
+        let mut game = GameBuilder::new(Box::new(view))
+            .human(Player::Black)
+            .ai(Box::new(SlowAI { steps: 3 }))
+            .build()
+            .unwrap();
+        let requests = update_requests.get();
+
+        for _ in 0..3 {
+            game.tick();
+            assert!(game.state().history.is_empty());
+            assert_eq!(game.state().current_player, Player::White);
+        }
+        assert_eq!(update_requests.get(), requests);
+
+        game.tick();
+        assert_eq!(game.state().history.len(), 1);
+        assert_eq!(game.state().current_player, Player::Black);
+        assert_eq!(update_requests.get(), requests + 1);
+    }
+
+    #[test]
+    fn test_first_player_from_config() {
+        let mut game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .config(GameConfig {
+                first_player: Player::Black,
+                ..GameConfig::default()
+            })
+            .build()
+            .unwrap();
+        game.tick();
+        assert_eq!(game.state().board.player_rings(Player::Black).count(), 1);
+
+        let replayed = game.state().replayed();
+        assert_eq!(replayed.current_player, Player::White);
+        assert_eq!(replayed.board.hash(), game.state().board.hash());
+    }
+
+    fn game_with_script(script: Vec<UiAction>) -> Game {
+        Game::new(Player::White, Box::new(HeadlessView::new(script)), Board::new()).unwrap()
+    }
+
+    #[test]
+    fn test_callbacks() {
+        use std::cell::Cell;
+        use std::rc::Rc;
 
+        // the view is also ticked while the AI moves
+        let script = vec![
+            UiAction::ActionAtCoord(HexCoord::new(0, 0)),
+            UiAction::NoAction,
+            UiAction::ActionAtCoord(HexCoord::new(0, 0)),
+            UiAction::ActionAtCoord(HexCoord::new(1, 1)),
+            UiAction::NoAction,
+            UiAction::Resign,
+        ];
+        let mut game = game_with_script(script);
+        let moves = Rc::new(Cell::new(0));
+        let changes = Rc::new(Cell::new(0));
+        let game_overs = Rc::new(Cell::new(0));
+        let (m, c, g) = (moves.clone(), changes.clone(), game_overs.clone());
+        game.set_on_move(move |_, state_changes| {
+            m.set(m.get() + 1);
+            c.set(c.get() + state_changes.len());
+        });
+        game.set_on_game_over(move |state| {
+            assert_eq!(state.current_phase, Phase::PlayerWon(Player::Black));
+            g.set(g.get() + 1);
+        });
+
+        // ring, AI ring, occupied field (no action), ring, AI ring, resign
+        for _ in 0..8 {
+            game.tick();
+        }
+        assert_eq!(moves.get(), 4);
+        assert_eq!(changes.get(), 4);
+        assert_eq!(game_overs.get(), 1);
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut game = game_with_script(vec![UiAction::Resign]);
+        game.tick();
+        assert_eq!(game.state().current_phase, Phase::PlayerWon(Player::Black));
+        assert_eq!(game.state().result(), GameResult::Black(WinReason::Resignation));
+        assert!(!game.resign(Player::Black));
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        let script = || {
+            vec![
+                UiAction::OfferDraw,
+                UiAction::ActionAtCoord(HexCoord::new(0, 0)),
+            ]
+        };
+
+        // the AI is not behind and plays on
+        let mut game = game_with_script(script());
+        game.tick();
+        assert_eq!(game.draw_offer, Some(Player::White));
+        game.tick();
+        game.tick();
+        assert_eq!(game.draw_offer, None);
+        assert_eq!(game.state().current_phase, Phase::PlaceRing);
+        assert_eq!(game.state().history.len(), 2);
+
+        // the AI is behind and accepts
+        let mut game = game_with_script(script());
+        game.state.points_white = 1;
+        for _ in 0..3 {
+            game.tick();
+        }
+        assert_eq!(game.state().current_phase, Phase::Draw);
+        assert_eq!(game.state().history.len(), 1);
+
+        // accepting without a pending offer does nothing
+        let mut game = game_with_script(vec![UiAction::AcceptDraw]);
+        game.tick();
+        assert_eq!(game.state().current_phase, Phase::PlaceRing);
+        assert!(game.offer_draw(Player::Black));
+        assert!(game.accept_draw(Player::White));
+        assert_eq!(game.state().current_phase, Phase::Draw);
+        assert_eq!(game.state().result(), GameResult::Draw(DrawReason::Agreement));
+
+        // in a hotseat game the offer lapses once the opponent moves instead
+        let script = vec![
+            UiAction::OfferDraw,
+            UiAction::ActionAtCoord(HexCoord::new(0, 0)),
+            UiAction::ActionAtCoord(HexCoord::new(1, 0)),
+            UiAction::AcceptDraw,
+        ];
+        let mut game = game_with_script(script);
+        game.mode = GameMode::HumanVsHuman;
+        game.tick();
+        game.tick();
+        assert_eq!(game.draw_offer, Some(Player::White));
+        game.tick();
+        assert_eq!(game.draw_offer, None);
+        assert!(!game.accept_draw(Player::Black));
+        game.tick();
+        assert_eq!(game.state().current_phase, Phase::PlaceRing);
+        assert_eq!(game.state().history.len(), 2);
+    }
+
+    #[test]
+    fn test_new_validates_start() {
+        let game = Game::new(
+            Player::White,
+            Box::new(HeadlessView::new(vec![])),
+            Board::new(),
+        );
+        assert!(game.is_ok());
+
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
+        let game = Game::new(Player::White, Box::new(HeadlessView::new(vec![])), board);
+        assert_eq!(game.err(), Some(SetupError::MarkersBeforeRings));
+    }
+
+    fn mid_game(seed: u64) -> Game {
+        let mut game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .human(Player::Black)
+            .mode(GameMode::HumanVsHuman)
+            .config(GameConfig {
+                first_player: Player::Black,
+                ..GameConfig::default()
+            })
+            .build()
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..40 {
+            let moves = game.state.legal_moves();
+            match moves.choose(&mut rng) {
+                Some(m) => m.execute(&mut game.state),
+                None => break,
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn test_save_and_load_session() {
+        let game = mid_game(5);
+        let loaded = Game::load(game.save(), Box::new(HeadlessView::new(vec![]))).unwrap();
+
+        assert_eq!(loaded.mode, GameMode::HumanVsHuman);
+        assert_eq!(loaded.human_player, Player::Black);
+        assert_eq!(loaded.config, game.config);
+        assert_eq!(loaded.state.snapshot(), game.state.snapshot());
+        let coords = |g: &Game| {
+            g.state
+                .legal_moves()
+                .iter()
+                .map(|a| a.coord())
+                .collect::<Vec<_>>()
+        };
+        assert!(!coords(&game).is_empty());
+        assert_eq!(coords(&loaded), coords(&game));
+    }
+
+    #[test]
+    fn test_load_keeps_config_rules() {
+        let config = GameConfig {
+            run_length: 4,
+            rings_per_player: 4,
+            ..GameConfig::default()
+        };
+        let game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .config(config)
+            .build()
+            .unwrap();
+        let blob = SessionBlob::decode(&game.save().encode()).unwrap();
+        let mut loaded = Game::load(blob, Box::new(HeadlessView::new(vec![]))).unwrap();
+        assert_eq!(loaded.config, config);
+
+        let board = &mut loaded.state.board;
+        assert_eq!(board.run_length(), 4);
+        for x in 0..4 {
+            board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(x, 0));
+        }
+        assert_eq!(board.runs(&Player::White).len(), 1);
+        for y in 1..=4 {
+            board.place(&Piece::Ring(Player::White), &HexCoord::new(0, y)).unwrap();
+        }
+        assert_eq!(
+            board.place(&Piece::Ring(Player::White), &HexCoord::new(-1, 1)),
+            Err(PlaceError::TooManyRings(Player::White))
+        );
+    }
+
+    #[test]
+    fn test_analyze_follows_winning_line() {
+        let mut game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .mode(GameMode::HumanVsHuman)
+            .build()
+            .unwrap();
+        let mut clicks = vec![(-2, 0), (0, -4), (0, 4), (-4, -4), (4, 4)];
+        clicks.extend([(-4, 0), (2, 4), (-4, -1), (-1, 3), (-3, -3)]);
+        // White walks a ring east leaving a run behind, Black walks one north east
+        for i in 0..5 {
+            clicks.extend([(i - 2, 0), (i - 1, 0)]);
+            if i < 4 {
+                clicks.extend([(i, i - 4), (i + 1, i - 3)]);
+            }
+        }
+        clicks.extend([(-2, 0), (3, 0)]);
+        for c in clicks {
+            game.state.execute_for_coord(&HexCoord::from(c)).unwrap();
+        }
+        assert_eq!(game.state().points_white, 1);
+
+        let weights = EvalWeights {
+            ring_score: 1,
+            mobility: 0,
+            marker_control: 0,
+            threats: 0,
+        };
+        let ai = MinimaxAI::new(Player::White, 0).with_weights(weights);
+        let evals = game.analyze(&ai);
+        assert_eq!(evals.len(), game.state().history.len());
+        assert!(evals.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(evals.last(), Some(&1));
+    }
+
+    #[test]
+    fn test_session_text_round_trip() {
+        let mut game = mid_game(5);
+        game.draw_offer = Some(Player::White);
+        let blob = game.save();
+        let text = blob.encode();
+        let decoded = SessionBlob::decode(&text).unwrap();
+        assert_eq!(decoded.encode(), text);
+        assert_eq!(decoded.config, blob.config);
+        assert_eq!(decoded.mode, blob.mode);
+        assert_eq!(decoded.human_player, blob.human_player);
+        assert_eq!(decoded.initial_board.encode(), blob.initial_board.encode());
+        assert_eq!(decoded.history, blob.history);
+        assert_eq!(decoded.phase, blob.phase);
+        assert_eq!(decoded.result, blob.result);
+        assert_eq!(decoded.draw_offer, Some(Player::White));
+
+        let loaded = Game::load(decoded, Box::new(HeadlessView::new(vec![]))).unwrap();
+        assert_eq!(loaded.state.snapshot(), game.state.snapshot());
+
+        game.resign(Player::Black);
+        let text = game.save().encode();
+        assert!(text.contains("result white resignation\n"));
+        assert_eq!(SessionBlob::decode(&text).unwrap().encode(), text);
+
+        assert_eq!(
+            SessionBlob::decode("mode chess").err(),
+            Some(LoadError::Malformed("mode chess".to_owned()))
+        );
+        let missing = text.replace("human black\n", "");
+        assert_eq!(
+            SessionBlob::decode(&missing).err(),
+            Some(LoadError::Missing("human"))
+        );
+        assert_eq!(
+            LoadError::IllegalAction(2).to_string(),
+            "move 3 is not legal"
+        );
+    }
+
+    #[test]
+    fn test_load_refuses_inconsistent_session() {
+        let mut game = mid_game(6);
+        game.resign(Player::White);
+        let loaded = Game::load(game.save(), Box::new(HeadlessView::new(vec![]))).unwrap();
+        assert_eq!(loaded.state.won_by(), Some(Player::Black));
+        assert_eq!(loaded.state.result(), GameResult::Black(WinReason::Resignation));
+
+        let mut blob = mid_game(6).save();
+        blob.history.swap(0, 1);
+        blob.history.insert(1, blob.history[0].clone());
+        assert!(matches!(
+            Game::load(blob, Box::new(HeadlessView::new(vec![]))),
+            Err(LoadError::IllegalAction(1))
+        ));
+
+        let mut blob = mid_game(6).save();
+        blob.phase = Phase::RemoveRing;
+        assert!(matches!(
+            Game::load(blob, Box::new(HeadlessView::new(vec![]))),
+            Err(LoadError::PhaseMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tick_recovers_empty_remove_run() {
+        let mut game = GameBuilder::new(Box::new(HeadlessView::new(vec![])))
+            .mode(GameMode::HumanVsHuman)
+            .build()
+            .unwrap();
+        game.state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 0));
+        game.state.set_phase(Phase::RemoveRun);
+        game.tick();
+        assert_eq!(game.state().current_phase, Phase::PlaceMarker);
+        assert_eq!(game.state().current_player, Player::Black);
+    }
 }
@@ -0,0 +1,227 @@
+use crate::core::actions::Action;
+use crate::core::ai::{Ai, AiStatus};
+use crate::core::command::Command;
+use crate::core::entities::Player;
+use crate::core::search::Search;
+use crate::core::state::State;
+
+// Weights of the evaluation terms, each applied to the difference between the
+// AI player and the opponent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EvalWeights {
+    // per removed ring, i.e. per point
+    pub ring_score: i32,
+    // per field the own rings can move to
+    pub mobility: i32,
+    // per marker on the board
+    pub marker_control: i32,
+    // per line one marker short of a run
+    pub threats: i32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            ring_score: 100000,
+            mobility: 1,
+            marker_control: 1,
+            threats: 30,
+        }
+    }
+}
+
+impl EvalWeights {
+    fn player_eval(&self, state: &State, player: Player) -> i32 {
+        let mobility = state.board.ring_mobility(player);
+        let markers = state.board.player_markers(player).count();
+        let threats = state
+            .board
+            .n_connected_markers(&player, state.board.run_length() - 1);
+
+        self.ring_score * state.get_score(&player) as i32
+            + self.mobility * mobility as i32
+            + self.marker_control * markers as i32
+            + self.threats * threats as i32
+    }
+
+    // Weighted difference between `player` and the opponent.
+    pub fn evaluate(&self, state: &State, player: Player) -> i32 {
+        self.player_eval(state, player) - self.player_eval(state, player.other())
+    }
+}
+
+pub struct MinimaxAI {
+    weights: EvalWeights,
+    search: Search,
+}
+
+impl MinimaxAI {
+    pub fn new(player: Player, max_depth: u32) -> Self {
+        MinimaxAI {
+            weights: EvalWeights::default(),
+            search: Search::new(player, max_depth),
+        }
+    }
+
+    pub fn with_weights(mut self, weights: EvalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    // Static evaluation from the AI player's point of view.
+    pub fn evaluate(&self, state: &State) -> i32 {
+        self.weights.evaluate(state, self.search.player())
+    }
+
+    // Root moves plus searched nodes of the last search.
+    pub fn evaluated_moves(&self) -> u32 {
+        self.search.evaluated_moves
+    }
+
+    // Line the AI expects from the position of the last best_action call,
+    // starting with the returned action.
+    pub fn pv(&self) -> Vec<Action> {
+        self.search.pv()
+    }
+
+    // On equal scores the earlier move in legal_moves() order is kept.
+    pub fn best_action(&mut self, state: &State) -> Option<Action> {
+        let (weights, player) = (self.weights, self.search.player());
+        self.search
+            .best_action(state, state.legal_moves(), |s| weights.evaluate(s, player))
+    }
+}
+
+impl Ai for MinimaxAI {
+    fn turn(&mut self, state: &mut State) {
+        if let Some(action) = self.best_action(state) {
+            action.execute(state);
+        }
+    }
+
+    // Searches one root move per step.
+    fn step(&mut self, state: &mut State) -> AiStatus {
+        let (weights, player) = (self.weights, self.search.player());
+        let best = self
+            .search
+            .step(state, || state.legal_moves(), |s| weights.evaluate(s, player));
+        match best {
+            None => AiStatus::Thinking,
+            Some(best) => {
+                if let Some(action) = best {
+                    action.execute(state);
+                }
+                AiStatus::Done
+            }
+        }
+    }
+
+    fn set_player(&mut self, player: Player) {
+        self.search.set_player(player);
+    }
+
+    fn evaluate(&self, state: &State) -> i32 {
+        MinimaxAI::evaluate(self, state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::actions::PlaceMarker;
+    use crate::core::board::Board;
+    use crate::core::entities::Piece;
+    use crate::core::state::Phase;
+
+    use super::*;
+
+    // White can jump a lone black marker to the north or a black marker that is
+    // part of a four to the south; both flip exactly one marker.
+    fn blocking_position() -> State {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &from);
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 1));
+        for x in -2..2 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(x, -1));
+        }
+        state.set_phase(Phase::PlaceMarker);
+        Action::from(PlaceMarker { coord: from }).execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn test_threat_weight_prefers_blocking() {
+        let state = blocking_position();
+        let weights = EvalWeights {
+            ring_score: 0,
+            mobility: 0,
+            marker_control: 1,
+            threats: 0,
+        };
+
+        let mut ai = MinimaxAI::new(Player::White, 0).with_weights(weights);
+        assert_eq!(ai.best_action(&state).unwrap().coord(), HexCoord::new(0, 2));
+
+        let mut ai = MinimaxAI::new(Player::White, 0).with_weights(EvalWeights {
+            threats: 10,
+            ..weights
+        });
+        assert_eq!(
+            ai.best_action(&state).unwrap().coord(),
+            HexCoord::new(0, -2)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_is_relative() {
+        let state = blocking_position();
+        let white = MinimaxAI::new(Player::White, 0);
+        let black = MinimaxAI::new(Player::Black, 0);
+        assert_eq!(white.evaluate(&state), -black.evaluate(&state));
+        assert!(white.evaluate(&state) < 0);
+    }
+
+    #[test]
+    fn test_steps_match_best_action() {
+        let state = blocking_position();
+        let mut ai = MinimaxAI::new(Player::White, 1);
+        let best = ai.best_action(&state).unwrap();
+        let n_moves = state.legal_moves().len();
+        assert!(n_moves > 1);
+
+        let mut stepped = state.clone();
+        let mut ai = MinimaxAI::new(Player::White, 1);
+        for _ in 1..n_moves {
+            assert_eq!(ai.step(&mut stepped), AiStatus::Thinking);
+            assert_eq!(stepped.history.len(), state.history.len());
+        }
+        assert_eq!(ai.step(&mut stepped), AiStatus::Done);
+        assert_eq!(stepped.history.last(), Some(&best));
+    }
+
+    #[test]
+    fn test_pv_is_legal_line() {
+        let mut state = blocking_position();
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, 2));
+        let mut ai = MinimaxAI::new(Player::White, 2);
+        let best = ai.best_action(&state).unwrap();
+        let pv = ai.pv();
+        assert_eq!(pv.len(), 3);
+        assert_eq!(pv[0].coord(), best.coord());
+
+        let mut replay = state.clone();
+        for action in pv {
+            assert!(action.is_legal(&replay), "{:?}", action);
+            action.execute(&mut replay);
+        }
+    }
+}
@@ -4,4 +4,15 @@ pub mod board;
 pub mod entities;
 pub mod state;
 pub mod ai;
-pub mod command;
\ No newline at end of file
+pub mod command;
+pub mod zobrist;
+pub mod book;
+pub mod symmetry;
+pub mod config;
+pub mod selfplay;
+pub mod minimax;
+pub mod search;
+pub mod tournament;
+pub mod ponder;
+pub mod puzzle;
+pub mod solver;
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::core::actions::Action;
+use crate::core::ai::{Ai, AiStatus};
+use crate::core::command::Command;
+use crate::core::state::State;
+
+// Searches the AI's replies to the opponent's possible moves while the opponent
+// is thinking, a few AI steps per tick. Replies are keyed by the position the
+// opponent's move leads to, so a correct prediction costs no search.
+pub struct Ponder {
+    // AI steps per tick
+    budget: usize,
+    // position the pending candidates were generated from
+    base_hash: Option<u64>,
+    pending: Vec<Action>,
+    // predicted position whose reply is being searched, keyed like `replies`
+    current: Option<(u64, State)>,
+    replies: HashMap<u64, Action>,
+}
+
+impl Ponder {
+    pub fn new(budget: usize) -> Self {
+        Ponder {
+            budget,
+            base_hash: None,
+            pending: vec![],
+            current: None,
+            replies: HashMap::new(),
+        }
+    }
+
+    // Only candidates that end the opponent's turn lead to a position the AI
+    // has to answer, others are skipped.
+    fn next_prediction(&mut self, state: &State) -> Option<(u64, State)> {
+        while let Some(candidate) = self.pending.pop() {
+            let mut predicted = state.clone();
+            candidate.execute(&mut predicted);
+            if predicted.current_player != state.current_player {
+                return Some((predicted.hash(), predicted));
+            }
+        }
+        None
+    }
+
+    pub fn step(&mut self, state: &State, ai: &mut dyn Ai) {
+        let hash = state.hash();
+        if self.base_hash != Some(hash) {
+            self.base_hash = Some(hash);
+            self.pending = state.legal_moves();
+            self.pending.reverse();
+            self.current = None;
+        }
+        for _ in 0..self.budget {
+            if self.current.is_none() {
+                self.current = self.next_prediction(state);
+            }
+            let Some((key, predicted)) = self.current.as_mut() else {
+                return;
+            };
+            let plies = predicted.history.len();
+            if ai.step(predicted) == AiStatus::Done {
+                if predicted.history.len() > plies {
+                    self.replies.insert(*key, predicted.history[plies].clone());
+                }
+                self.current = None;
+            }
+        }
+    }
+
+    // The pondered reply to the current position, if it was predicted. Every
+    // call starts pondering afresh, hit or miss.
+    pub fn take(&mut self, state: &State) -> Option<Action> {
+        let reply = self.replies.remove(&state.hash());
+        self.replies.clear();
+        self.base_hash = None;
+        self.pending.clear();
+        self.current = None;
+        reply.filter(|a| a.is_legal(state))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::actions::PlaceMarker;
+    use crate::core::board::Board;
+    use crate::core::entities::{Piece, Player};
+    use crate::core::state::Phase;
+
+    use super::*;
+
+    // Plays the first legal move and counts its searches.
+    struct FirstMoveAI {
+        searches: usize,
+    }
+
+    impl Ai for FirstMoveAI {
+        fn turn(&mut self, state: &mut State) {
+            self.searches += 1;
+            if let Some(action) = state.legal_moves().into_iter().next() {
+                action.execute(state);
+            }
+        }
+    }
+
+    // Needs two steps per move.
+    struct TwoStepAI {
+        steps: usize,
+    }
+
+    impl Ai for TwoStepAI {
+        fn turn(&mut self, state: &mut State) {
+            FirstMoveAI { searches: 0 }.turn(state);
+        }
+
+        fn step(&mut self, state: &mut State) -> AiStatus {
+            self.steps += 1;
+            if self.steps % 2 == 1 {
+                return AiStatus::Thinking;
+            }
+            self.turn(state);
+            AiStatus::Done
+        }
+    }
+
+    // White is moving the ring at the center.
+    fn human_to_move() -> State {
+        let mut state = State::new(Board::new());
+        let white = HexCoord::new(0, 0);
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &white);
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(2, 1));
+        state.set_phase(Phase::PlaceMarker);
+        Action::from(PlaceMarker { coord: white }).execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn test_ponder_hit_skips_search() {
+        let mut state = human_to_move();
+
+        let mut ai = FirstMoveAI { searches: 0 };
+        let mut ponder = Ponder::new(100);
+        ponder.step(&state, &mut ai);
+        let searched = ai.searches;
+        assert_eq!(searched, state.legal_moves().len());
+
+        let human_move = state.legal_moves()[1].clone();
+        human_move.execute(&mut state);
+        assert_eq!(state.current_player, Player::Black);
+
+        let mut expected = state.clone();
+        FirstMoveAI { searches: 0 }.turn(&mut expected);
+        let reply = ponder.take(&state).unwrap();
+        assert_eq!(ai.searches, searched);
+        assert_eq!(reply.coord(), expected.history.last().unwrap().coord());
+
+        // a position that was not pondered has no reply
+        assert!(ponder.take(&state).is_none());
+    }
+
+    #[test]
+    fn test_ponder_miss_clears_replies() {
+        let state = human_to_move();
+        let mut ponder = Ponder::new(100);
+        ponder.step(&state, &mut FirstMoveAI { searches: 0 });
+        assert!(!ponder.replies.is_empty());
+
+        let mut predicted = state.clone();
+        state.legal_moves()[0].execute(&mut predicted);
+        assert!(ponder.take(&state).is_none());
+        assert!(ponder.replies.is_empty() && ponder.pending.is_empty());
+        // the reply for the predicted position went with the miss
+        assert!(ponder.take(&predicted).is_none());
+    }
+
+    #[test]
+    fn test_ponder_steps_the_ai() {
+        let state = human_to_move();
+        let mut ai = TwoStepAI { steps: 0 };
+        let mut ponder = Ponder::new(1);
+        ponder.step(&state, &mut ai);
+        assert_eq!(ai.steps, 1);
+        assert!(ponder.replies.is_empty());
+
+        ponder.step(&state, &mut ai);
+        assert_eq!(ai.steps, 2);
+        assert_eq!(ponder.replies.len(), 1);
+    }
+}
@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+use crate::common::coord::HexCoord;
+use crate::common::notation::parse_label;
+use crate::core::board::{Board, SetupError};
+use crate::core::entities::{Piece, Player};
+use crate::core::state::{Phase, State, StateChange};
+
+// What the side to move has to achieve with the solution clicks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    // gain this many points
+    Score(usize),
+    // complete at least one run of their own
+    FormRun,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    UnknownKey(String),
+    BadLabel(String),
+    BadValue(String),
+    MissingGoal,
+    Setup(SetupError),
+}
+
+// A position plus a goal for the side to move. The text format has one
+// "key value..." entry per line, fields in the official notation:
+//
+//   to_move white
+//   goal score 1
+//   white rings f6 c3
+//   white markers b4 c4
+//   black rings k7
+//
+// Lines starting with '#' are comments. The side to move starts by placing a
+// marker unless a "phase" line says remove-run or remove-ring.
+pub struct Puzzle {
+    pub state: State,
+    pub goal: Goal,
+}
+
+fn parse_player(s: &str) -> Result<Player, PuzzleError> {
+    match s {
+        "white" => Ok(Player::White),
+        "black" => Ok(Player::Black),
+        _ => Err(PuzzleError::BadValue(s.to_owned())),
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut board = Board::new();
+        let mut to_move = Player::White;
+        let mut phase = Phase::PlaceMarker;
+        let mut goal = None;
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["to_move", player] => to_move = parse_player(player)?,
+                ["phase", "place-marker"] => phase = Phase::PlaceMarker,
+                ["phase", "remove-run"] => phase = Phase::RemoveRun,
+                ["phase", "remove-ring"] => phase = Phase::RemoveRing,
+                ["goal", "score", n] => {
+                    let n = n
+                        .parse()
+                        .map_err(|_| PuzzleError::BadValue(n.to_string()))?;
+                    goal = Some(Goal::Score(n));
+                }
+                ["goal", "run"] => goal = Some(Goal::FormRun),
+                [player, kind @ ("rings" | "markers"), labels @ ..] => {
+                    let player = parse_player(player)?;
+                    let piece = if *kind == "rings" {
+                        Piece::Ring(player)
+                    } else {
+                        Piece::Marker(player)
+                    };
+                    for label in labels {
+                        let c = parse_label(label)
+                            .filter(|c| board.is_on_board(c))
+                            .ok_or_else(|| PuzzleError::BadLabel(label.to_string()))?;
+                        board.place_unchecked(&piece, &c);
+                    }
+                }
+                _ => return Err(PuzzleError::UnknownKey(line.to_owned())),
+            }
+        }
+
+        let state = State::from_position(board, to_move, phase).map_err(PuzzleError::Setup)?;
+        Ok(Puzzle {
+            state,
+            goal: goal.ok_or(PuzzleError::MissingGoal)?,
+        })
+    }
+}
+
+impl Puzzle {
+    // Plays the clicks on a copy of the position; every click has to be a legal
+    // move and the goal has to be reached once they are done.
+    pub fn check(&self, solution: &[HexCoord]) -> bool {
+        let player = self.state.current_player;
+        let mut state = self.state.clone();
+        let mut run_formed = false;
+        for click in solution {
+            if state.execute_for_coord(click).is_err() {
+                return false;
+            }
+            run_formed |= state
+                .last_state_change
+                .iter()
+                .any(|sc| matches!(sc, StateChange::RunFormed(p, _) if *p == player));
+        }
+        match self.goal {
+            Goal::Score(n) => state.get_score(&player) >= self.state.get_score(&player) + n,
+            Goal::FormRun => run_formed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The marker on f6 has to be flipped to complete b6-f6.
+    const SCORE_IN_ONE: &str = "
+        # White to move and score
+        to_move white
+        goal score 1
+        white rings f5 e9
+        white markers b6 c6 d6 e6
+        black rings c3
+        black markers f6
+    ";
+
+    fn clicks(labels: &[&str]) -> Vec<HexCoord> {
+        labels.iter().map(|l| parse_label(l).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_score_in_one() {
+        let puzzle: Puzzle = SCORE_IN_ONE.parse().unwrap();
+        assert_eq!(puzzle.goal, Goal::Score(1));
+        assert_eq!(puzzle.state.current_player, Player::White);
+
+        assert!(puzzle.check(&clicks(&["f5", "f7", "b6", "e9"])));
+        // moving away flips nothing
+        assert!(!puzzle.check(&clicks(&["f5", "f4"])));
+        // the run alone doesn't score yet
+        assert!(!puzzle.check(&clicks(&["f5", "f7", "b6"])));
+        assert!(!puzzle.check(&clicks(&["c3"])));
+
+        // replays start from the puzzle position, not from an empty board
+        let mut state = puzzle.state.clone();
+        for click in clicks(&["f5", "f7"]) {
+            state.execute_for_coord(&click).unwrap();
+        }
+        assert_eq!(state.replayed().snapshot(), state.snapshot());
+        assert_eq!(state.replay_to(0).snapshot(), puzzle.state.snapshot());
+
+        let run_puzzle: Puzzle = SCORE_IN_ONE.replace("score 1", "run").parse().unwrap();
+        assert!(run_puzzle.check(&clicks(&["f5", "f7"])));
+        assert!(!run_puzzle.check(&clicks(&["f5", "f4"])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "goal run\nwhite rings z9".parse::<Puzzle>().err(),
+            Some(PuzzleError::BadLabel("z9".to_owned()))
+        );
+        assert_eq!(
+            "white rings f6".parse::<Puzzle>().err(),
+            Some(PuzzleError::MissingGoal)
+        );
+        assert_eq!(
+            "goal run\nto_move red".parse::<Puzzle>().err(),
+            Some(PuzzleError::BadValue("red".to_owned()))
+        );
+        assert_eq!(
+            "goal run\nflip f6".parse::<Puzzle>().err(),
+            Some(PuzzleError::UnknownKey("flip f6".to_owned()))
+        );
+    }
+}
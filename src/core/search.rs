@@ -0,0 +1,201 @@
+use crate::core::actions::Action;
+use crate::core::entities::Player;
+use crate::core::state::State;
+
+// Root moves of a search in progress, searched one per step().
+struct RootSearch {
+    // history length and board hash of the searched position
+    position: (usize, u64),
+    moves: Vec<Action>,
+    next: usize,
+    best: Option<(i32, Action)>,
+}
+
+// Alpha-beta search for `player`, `max_depth` plies below each root move. The
+// static evaluation is passed in by the AI and scores a position from
+// `player`'s point of view.
+pub struct Search {
+    player: Player,
+    max_depth: u32,
+    pv: Vec<Action>,
+    root: Option<RootSearch>,
+    // root moves plus searched nodes of the last search
+    pub evaluated_moves: u32,
+}
+
+impl Search {
+    pub fn new(player: Player, max_depth: u32) -> Self {
+        Search {
+            player,
+            max_depth,
+            pv: vec![],
+            root: None,
+            evaluated_moves: 0,
+        }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn set_player(&mut self, player: Player) {
+        self.player = player;
+        self.root = None;
+    }
+
+    // Line expected from the position of the last finished search, starting
+    // with the returned action.
+    pub fn pv(&self) -> Vec<Action> {
+        self.pv.clone()
+    }
+
+    // On equal scores the earlier of `moves` is kept.
+    pub fn best_action(
+        &mut self,
+        state: &State,
+        moves: Vec<Action>,
+        eval: impl Fn(&State) -> i32,
+    ) -> Option<Action> {
+        self.root = None;
+        let mut moves = Some(moves);
+        loop {
+            if let Some(best) = self.step(state, || moves.take().unwrap_or_default(), &eval) {
+                return best;
+            }
+        }
+    }
+
+    // Searches one root move of `state`, starting a new search over `moves()`
+    // if the position changed. Returns the best action with the last root move.
+    pub fn step(
+        &mut self,
+        state: &State,
+        moves: impl FnOnce() -> Vec<Action>,
+        eval: impl Fn(&State) -> i32,
+    ) -> Option<Option<Action>> {
+        let position = (state.history.len(), state.board.hash());
+        if self.root.as_ref().is_none_or(|r| r.position != position) {
+            self.evaluated_moves = 0;
+            self.pv.clear();
+            self.root = Some(RootSearch {
+                position,
+                moves: moves(),
+                next: 0,
+                best: None,
+            });
+        }
+        let root = self.root.as_mut().unwrap();
+        let Some(action) = root.moves.get(root.next).cloned() else {
+            // no moves at all
+            self.root = None;
+            return Some(None);
+        };
+        root.next += 1;
+
+        self.evaluated_moves += 1;
+        let mut state = state.clone();
+        state.make(&action);
+        let mut line = vec![];
+        let depth = self.max_depth;
+        let score = self.alpha_beta(&mut state, depth, i32::MIN, i32::MAX, &eval, &mut line);
+        let root = self.root.as_mut().unwrap();
+        if root.best.as_ref().is_none_or(|(s, _)| score > *s) {
+            self.pv = std::iter::once(action.clone()).chain(line).collect();
+            root.best = Some((score, action));
+        }
+        if root.next < root.moves.len() {
+            return None;
+        }
+        let best = self.root.take().unwrap().best;
+        Some(best.map(|(_, action)| action))
+    }
+
+    // `line` receives the best continuation found below this node.
+    fn alpha_beta(
+        &mut self,
+        state: &mut State,
+        depth: u32,
+        mut alpha: i32,
+        mut beta: i32,
+        eval: &impl Fn(&State) -> i32,
+        line: &mut Vec<Action>,
+    ) -> i32 {
+        if state.won_by().is_some() || depth == 0 {
+            return eval(state);
+        }
+        let moves = state.legal_moves();
+        if moves.is_empty() {
+            return eval(state);
+        }
+
+        let maximizing = state.current_player == self.player;
+        let mut best = if maximizing { i32::MIN } else { i32::MAX };
+        for m in moves {
+            self.evaluated_moves += 1;
+            state.make(&m);
+            let mut child_line = vec![];
+            let value = self.alpha_beta(state, depth - 1, alpha, beta, eval, &mut child_line);
+            state.unmake();
+
+            let improved = if maximizing { value > best } else { value < best };
+            if improved || line.is_empty() {
+                line.clear();
+                line.push(m);
+                line.append(&mut child_line);
+            }
+            if maximizing {
+                best = best.max(value);
+                alpha = alpha.max(best);
+            } else {
+                best = best.min(value);
+                beta = beta.min(best);
+            }
+            if beta <= alpha {
+                break;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::actions::PlaceMarker;
+    use crate::core::board::Board;
+    use crate::core::command::Command;
+    use crate::core::entities::Piece;
+    use crate::core::state::Phase;
+
+    use super::*;
+
+    // White has placed a marker at the center and has to move the ring there.
+    fn move_ring_position() -> State {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &from);
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, 2));
+        state.set_phase(Phase::PlaceMarker);
+        Action::from(PlaceMarker { coord: from }).execute(&mut state);
+        state
+    }
+
+    #[test]
+    fn test_search_follows_evaluation() {
+        let state = move_ring_position();
+        let ring_x = |s: &State| s.board.ring_coords(Player::White)[0].0 as i32;
+
+        let mut search = Search::new(Player::White, 0);
+        let east = search.best_action(&state, state.legal_moves(), ring_x).unwrap();
+        let west = search
+            .best_action(&state, state.legal_moves(), |s| -ring_x(s))
+            .unwrap();
+        assert_eq!(east.coord().0, 4);
+        assert_eq!(west.coord().0, -4);
+        assert_eq!(search.evaluated_moves, state.legal_moves().len() as u32);
+    }
+}
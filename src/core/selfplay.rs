@@ -0,0 +1,93 @@
+use crate::core::actions::Action;
+use crate::core::ai::Ai;
+use crate::core::board::SetupError;
+use crate::core::config::GameConfig;
+use crate::core::entities::Player;
+use crate::core::state::State;
+
+#[derive(Debug, Clone)]
+pub struct SelfPlayRecord {
+    pub winner: Option<Player>,
+    pub points_white: usize,
+    pub points_black: usize,
+    pub moves: Vec<Action>,
+    pub hit_ply_cap: bool,
+}
+
+// Plays `white` against `black` from the empty board. Games that hit the ply cap,
+// or where the player to move has no legal move, are decided by score.
+pub fn self_play(
+    white: &mut dyn Ai,
+    black: &mut dyn Ai,
+    config: GameConfig,
+    seed: u64,
+) -> Result<SelfPlayRecord, SetupError> {
+    white.seed(seed);
+    black.seed(seed.wrapping_add(1));
+
+    let mut state = State::from_config(&config)?;
+    let mut hit_ply_cap = false;
+    while state.won_by().is_none() {
+        if state.history.len() >= config.max_plies {
+            hit_ply_cap = true;
+            break;
+        }
+        let plies = state.history.len();
+        match state.current_player {
+            Player::White => white.turn(&mut state),
+            Player::Black => black.turn(&mut state),
+        }
+        if state.history.len() == plies {
+            break;
+        }
+    }
+
+    let winner = state.won_by().or_else(|| state.leader());
+
+    Ok(SelfPlayRecord {
+        winner,
+        points_white: state.points_white,
+        points_black: state.points_black,
+        moves: state.history,
+        hit_ply_cap,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::ai::RandomAI;
+    use crate::core::command::Command;
+
+    use super::*;
+
+    fn play(seed: u64, config: GameConfig) -> SelfPlayRecord {
+        let mut white = RandomAI::new(Player::White, 1);
+        let mut black = RandomAI::new(Player::Black, 1);
+        self_play(&mut white, &mut black, config, seed).unwrap()
+    }
+
+    #[test]
+    fn test_self_play_terminates() {
+        let config = GameConfig::default();
+        let result = play(3, config);
+        assert!(result.moves.len() <= config.max_plies);
+        assert!(!result.hit_ply_cap);
+        assert!(result.winner.is_some());
+
+        let again = play(3, config);
+        let coords = |r: &SelfPlayRecord| r.moves.iter().map(|a| a.coord()).collect::<Vec<_>>();
+        assert_eq!(coords(&result), coords(&again));
+    }
+
+    #[test]
+    fn test_ply_cap_decides_by_score() {
+        let config = GameConfig {
+            max_plies: 12,
+            ..GameConfig::default()
+        };
+        let result = play(1, config);
+        assert!(result.hit_ply_cap);
+        assert_eq!(result.moves.len(), 12);
+        assert_eq!(result.winner, None);
+    }
+}
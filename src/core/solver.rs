@@ -0,0 +1,111 @@
+use crate::core::actions::Action;
+use crate::core::entities::Player;
+use crate::core::state::{Phase, State};
+
+// A forced win for the side to move within `turns` of their own turns, as the
+// actions of both players up to the winning ring removal. Every defence is
+// tried; the line follows the first one in legal_moves() order. Placing a ring
+// counts as a turn, so setup positions are searched to a bounded depth too.
+pub fn solve(state: &State, turns: usize) -> Option<Vec<Action>> {
+    let mut state = state.clone();
+    let attacker = state.current_player;
+    let mut line = vec![];
+    search(&mut state, attacker, turns, &mut line).then_some(line)
+}
+
+fn search(state: &mut State, attacker: Player, turns: usize, line: &mut Vec<Action>) -> bool {
+    if let Some(winner) = state.won_by() {
+        return winner == attacker;
+    }
+    let attacking = state.current_player == attacker;
+    // a turn starts with placing a ring or a marker, removals still belong to
+    // the last one
+    let starts_turn = attacking
+        && matches!(state.current_phase, Phase::PlaceRing | Phase::PlaceMarker);
+    if starts_turn && turns == 0 {
+        return false;
+    }
+    let turns = if starts_turn { turns - 1 } else { turns };
+
+    let moves = state.legal_moves();
+    if moves.is_empty() {
+        return false;
+    }
+    for action in moves {
+        let mut rest = vec![];
+        state.make(&action);
+        let won = search(state, attacker, turns, &mut rest);
+        state.unmake();
+        if won && (attacking || line.is_empty()) {
+            line.clear();
+            line.push(action);
+            line.append(&mut rest);
+        }
+        if attacking && won {
+            return true;
+        }
+        if !attacking && !won {
+            line.clear();
+            return false;
+        }
+    }
+    !attacking
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::board::Board;
+    use crate::core::command::Command;
+    use crate::core::entities::Piece;
+
+    use super::*;
+
+    // White is one run short of winning: placing at (2, 0) and moving that ring
+    // north completes (-2, 0)..(2, 0).
+    fn match_point() -> State {
+        let mut state = State::new(Board::new());
+        for i in -2..=1 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        for (player, c) in [
+            (Player::White, HexCoord::new(2, 0)),
+            (Player::White, HexCoord::new(-3, 2)),
+            (Player::Black, HexCoord::new(-3, -3)),
+        ] {
+            state.board.place_unchecked(&Piece::Ring(player), &c);
+        }
+        state.set_phase(Phase::PlaceMarker);
+        state.points_white = 2;
+        state
+    }
+
+    #[test]
+    fn test_finds_single_turn_win() {
+        let state = match_point();
+        let line = solve(&state, 1).unwrap();
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[0].coord(), HexCoord::new(2, 0));
+
+        let mut replay = state.clone();
+        for action in &line {
+            assert!(action.is_legal(&replay), "{:?}", action);
+            action.execute(&mut replay);
+        }
+        assert_eq!(replay.won_by(), Some(Player::White));
+
+        assert!(solve(&state, 0).is_none());
+        let mut short = match_point();
+        short.points_white = 1;
+        assert!(solve(&short, 1).is_none());
+    }
+
+    #[test]
+    fn test_ring_placements_count_as_turns() {
+        let state = State::new(Board::new());
+        assert!(solve(&state, 0).is_none());
+        assert!(solve(&state, 1).is_none());
+    }
+}
@@ -1,11 +1,17 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
 use crate::common::coord::*;
+use crate::common::notation::coord_label;
 use crate::core::board::*;
+use crate::core::config::GameConfig;
 use crate::core::entities::*;
+use crate::core::zobrist;
 
 use super::actions::*;
 use super::command::*;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Phase {
     PlaceRing,
     PlaceMarker,
@@ -13,7 +19,79 @@ pub enum Phase {
     RemoveRun,
     RemoveRing,
     PlayerWon(Player),
+    Draw,
+}
+
+impl Phase {
+    pub fn describe(&self, player: Player) -> String {
+        match self {
+            Phase::PlayerWon(_) | Phase::Draw => self.to_string(),
+            _ => format!("{} ({} to move)", self, player),
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::PlaceRing => write!(f, "Place ring"),
+            Phase::PlaceMarker => write!(f, "Place marker"),
+            Phase::MoveRing(from) => write!(f, "Move ring from {}", coord_label(from)),
+            Phase::RemoveRun => write!(f, "Remove run"),
+            Phase::RemoveRing => write!(f, "Remove ring"),
+            Phase::PlayerWon(player) => write!(f, "{} won", player),
+            Phase::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+// Why an action or click could not be applied.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum GameError {
+    GameOver,
+    WrongPhase(Phase),
+    OffBoard(HexCoord),
+    Occupied(HexCoord),
+    NotYourPiece(HexCoord),
+    NoSuchMove(HexCoord),
+    NoHistory,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum WinReason {
+    // the winner removed their last ring needed to win
+    RingsCaptured,
+    Resignation,
+    Timeout,
+    // the opponent could not move
+    Stalemate,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DrawReason {
+    Agreement,
+    Repetition,
+    // the phase was set to Draw without going through end()
+    Unknown,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum GameResult {
+    White(WinReason),
+    Black(WinReason),
+    Draw(DrawReason),
+    Ongoing,
 }
+
+impl GameResult {
+    pub fn win(player: Player, reason: WinReason) -> Self {
+        match player {
+            Player::White => GameResult::White(reason),
+            Player::Black => GameResult::Black(reason),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum StateChange {
     RingPlaced(Player, HexCoord),
@@ -22,6 +100,107 @@ pub enum StateChange {
     MarkerPlaced(Player, HexCoord),
     MarkerRemoved(Player, HexCoord),
     RingRemoved(Player, HexCoord),
+    // a run that the ring move completed, one per run
+    RunFormed(Player, Vec<HexCoord>),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveSummary {
+    pub flips: usize,
+    pub placements: usize,
+    pub removals: usize,
+    pub run_formed: bool,
+}
+
+impl fmt::Display for MoveSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let mut parts = vec![];
+        if self.placements > 0 {
+            parts.push(format!(
+                "placed {} piece{}",
+                self.placements,
+                plural(self.placements)
+            ));
+        }
+        if self.flips > 0 {
+            parts.push(format!(
+                "flipped {} marker{}",
+                self.flips,
+                plural(self.flips)
+            ));
+        }
+        if self.removals > 0 {
+            parts.push(format!(
+                "removed {} piece{}",
+                self.removals,
+                plural(self.removals)
+            ));
+        }
+        if self.run_formed {
+            parts.push("formed a run".to_owned());
+        }
+        if parts.is_empty() {
+            return write!(f, "no changes");
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+// Cheap comparable summary of a State for regression tests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateSnapshot {
+    pub census: (usize, usize, usize, usize),
+    pub board_hash: u64,
+    pub phase: Phase,
+    pub player: Player,
+    pub points_white: usize,
+    pub points_black: usize,
+    pub runs_white: BTreeSet<Vec<HexCoord>>,
+    pub runs_black: BTreeSet<Vec<HexCoord>>,
+}
+
+impl StateSnapshot {
+    pub fn diff(&self, other: &StateSnapshot) -> Vec<String> {
+        let mut res = vec![];
+        if self.census != other.census {
+            res.push(format!("census: {:?} != {:?}", self.census, other.census));
+        }
+        if self.board_hash != other.board_hash {
+            res.push("board differs".to_owned());
+        }
+        if self.phase != other.phase {
+            res.push(format!("phase: {} != {}", self.phase, other.phase));
+        }
+        if self.player != other.player {
+            res.push(format!("player: {} != {}", self.player, other.player));
+        }
+        if self.points_white != other.points_white {
+            res.push(format!(
+                "white points: {} != {}",
+                self.points_white, other.points_white
+            ));
+        }
+        if self.points_black != other.points_black {
+            res.push(format!(
+                "black points: {} != {}",
+                self.points_black, other.points_black
+            ));
+        }
+        if self.runs_white != other.runs_white {
+            res.push(format!(
+                "white runs: {:?} != {:?}",
+                self.runs_white, other.runs_white
+            ));
+        }
+        if self.runs_black != other.runs_black {
+            res.push(format!(
+                "black runs: {:?} != {:?}",
+                self.runs_black, other.runs_black
+            ));
+        }
+        res
+    }
 }
 
 #[derive(Clone)]
@@ -36,21 +215,59 @@ pub struct State {
     pub runs_black: Vec<Vec<HexCoord>>,
     pub history: Vec<Action>,
     pub last_state_change: Vec<StateChange>,
+    // board the game started from, replaying history on it gives the current board
+    pub initial_board: Board,
+    pub first_player: Player,
+    // PlaceRing unless the game started from a position, see from_position()
+    initial_phase: Phase,
+    // how often each position (by hash()) was reached by an executed action
+    position_counts: HashMap<u64, u8>,
+    // set by end() for results the phase alone doesn't explain
+    outcome: Option<GameResult>,
 }
 
 impl State {
     pub fn new(board: Board) -> Self {
-        State {
+        debug_assert!(
+            board.validate_for_phase(Phase::PlaceRing).is_ok(),
+            "invalid initial board"
+        );
+        State::at_position(board, Player::White, Phase::PlaceRing)
+    }
+
+    // A game starting from a set up position, e.g. a puzzle, with `player` to
+    // play `phase`. Replays start from this position as well.
+    pub fn from_position(board: Board, player: Player, phase: Phase) -> Result<Self, SetupError> {
+        board.validate_for_phase(phase)?;
+        Ok(State::at_position(board, player, phase))
+    }
+
+    fn at_position(board: Board, player: Player, phase: Phase) -> Self {
+        let mut state = State {
+            initial_board: board.clone(),
             board,
-            current_player: Player::White,
-            current_phase: Phase::PlaceRing,
+            first_player: player,
+            current_player: player,
+            current_phase: phase,
+            initial_phase: phase,
             points_white: 0,
             points_black: 0,
             runs_white: vec![],
             runs_black: vec![],
             history: vec![],
             last_state_change: vec![],
-        }
+            position_counts: HashMap::new(),
+            outcome: None,
+        };
+        state.compute_runs();
+        state
+    }
+
+    pub fn from_config(config: &GameConfig) -> Result<Self, SetupError> {
+        let mut state = State::new(Board::from_config(config)?);
+        state.first_player = config.first_player;
+        state.current_player = config.first_player;
+        Ok(state)
     }
 
     pub fn new_action(&mut self) {
@@ -68,10 +285,10 @@ impl State {
 
         if let Some(piece) = removed {
             if piece.is_marker() {
-                self.push_state_change(StateChange::MarkerRemoved(piece.owner(), *coord));
+                self.push_state_change(StateChange::MarkerRemoved(piece.player(), *coord));
             }
             if piece.is_ring() {
-                self.push_state_change(StateChange::RingRemoved(piece.owner(), *coord));
+                self.push_state_change(StateChange::RingRemoved(piece.player(), *coord));
             }
         }
     }
@@ -102,13 +319,13 @@ impl State {
     pub fn place_marker(&mut self, player: &Player, coord: &HexCoord) {
         let piece = Piece::Marker(*player);
         let removed = self.board.place_unchecked(&piece, coord);
-        self.push_state_change(StateChange::RingPlaced(*player, *coord));
+        self.push_state_change(StateChange::MarkerPlaced(*player, *coord));
         if let Some(piece) = removed {
             if piece.is_marker() {
-                self.push_state_change(StateChange::MarkerRemoved(piece.owner(), *coord));
+                self.push_state_change(StateChange::MarkerRemoved(piece.player(), *coord));
             }
             if piece.is_ring() {
-                self.push_state_change(StateChange::RingRemoved(piece.owner(), *coord));
+                self.push_state_change(StateChange::RingRemoved(piece.player(), *coord));
             }
         }
     }
@@ -125,7 +342,91 @@ impl State {
     }
 
     pub fn legal_moves(&self) -> Vec<Action> {
+        self.legal_moves_for(self.current_player)
+    }
+
+    // Moves `player` would have in the current position if it were their turn.
+    // MoveRing(from) belongs to the player who placed the marker, so the other
+    // player is considered to be placing a marker instead.
+    pub fn legal_moves_for(&self, player: Player) -> Vec<Action> {
+        let phase = match self.current_phase {
+            Phase::MoveRing(_) if player != self.current_player => Phase::PlaceMarker,
+            phase => phase,
+        };
+        self.moves_in_phase(player, phase)
+    }
+
+    // Legal moves as a JSON array for external UIs, e.g.
+    // [{"type":"move_ring","from":[0,0],"to":[0,2],"flips":1}]
+    // Coordinates are [q, r] pairs as in HexCoord.
+    pub fn legal_moves_json(&self) -> String {
+        let coord = |c: &HexCoord| format!("[{},{}]", c.0, c.1);
+        let entries: Vec<String> = self
+            .legal_moves()
+            .iter()
+            .map(|action| match action {
+                Action::PlaceRing(a) => {
+                    format!(r#"{{"type":"place_ring","coord":{}}}"#, coord(&a.coord))
+                }
+                Action::PlaceMarker(a) => {
+                    format!(r#"{{"type":"place_marker","coord":{}}}"#, coord(&a.coord))
+                }
+                Action::MoveRing(a) => format!(
+                    r#"{{"type":"move_ring","from":{},"to":{},"flips":{}}}"#,
+                    coord(&a.from),
+                    coord(&a.to),
+                    self.board.flip_count(&a.from, &a.to)
+                ),
+                Action::RemoveRun(a) => format!(
+                    r#"{{"type":"remove_run","coord":{},"run":[{}]}}"#,
+                    coord(&a.coord),
+                    a.run.iter().map(coord).collect::<Vec<_>>().join(",")
+                ),
+                Action::RemoveRing(a) => {
+                    format!(r#"{{"type":"remove_ring","coord":{}}}"#, coord(&a.coord))
+                }
+            })
+            .collect();
+        format!("[{}]", entries.join(","))
+    }
+
+    // Legal moves grouped into labelled choices for a menu: one entry per run,
+    // ring or destination, a single entry when any field will do.
+    pub fn grouped_legal_moves(&self) -> Vec<(String, Vec<Action>)> {
+        let moves = self.legal_moves();
         match self.current_phase {
+            Phase::PlaceRing | Phase::PlaceMarker if !moves.is_empty() => {
+                let label = match self.current_phase {
+                    Phase::PlaceRing => "Place ring",
+                    _ => "Place marker",
+                };
+                vec![(label.to_string(), moves)]
+            }
+            _ => moves
+                .into_iter()
+                .enumerate()
+                .map(|(idx, action)| {
+                    let label = match &action {
+                        Action::MoveRing(m) => format!("Move ring to {}", coord_label(&m.to)),
+                        Action::RemoveRun(r) => format!(
+                            "Remove run {} ({}-{})",
+                            (b'A' + idx as u8) as char,
+                            coord_label(&r.run[0]),
+                            coord_label(r.run.last().unwrap())
+                        ),
+                        Action::RemoveRing(r) => {
+                            format!("Remove ring at {}", coord_label(&r.coord))
+                        }
+                        action => coord_label(&action.coord()),
+                    };
+                    (label, vec![action])
+                })
+                .collect(),
+        }
+    }
+
+    fn moves_in_phase(&self, player: Player, phase: Phase) -> Vec<Action> {
+        match phase {
             Phase::PlaceRing => self
                 .board
                 .board_coords()
@@ -133,10 +434,13 @@ impl State {
                 .filter(|x| self.board.occupied(x).is_none())
                 .map(|c| Action::from(PlaceRing { coord: *c }))
                 .collect(),
+            Phase::PlaceMarker if self.board.markers_remaining() == 0 => Vec::new(),
+            // ring_coords is sorted, which keeps move generation reproducible
             Phase::PlaceMarker => self
                 .board
-                .player_rings(self.current_player)
-                .map(|c| Action::from(PlaceMarker { coord: *c }))
+                .ring_coords(player)
+                .into_iter()
+                .map(|c| Action::from(PlaceMarker { coord: c }))
                 .collect::<Vec<Action>>(),
             Phase::MoveRing(from) => self
                 .board
@@ -144,7 +448,7 @@ impl State {
                 .iter()
                 .map(|c| {
                     Action::from(MoveRing {
-                        player: self.current_player,
+                        player,
                         from,
                         to: *c,
                     })
@@ -152,7 +456,7 @@ impl State {
                 .collect(),
             // TODO: this does not always work for multiple simultaneous runs!!
             Phase::RemoveRun => self
-                .current_player_runs()
+                .player_runs(player)
                 .iter()
                 .enumerate()
                 .map(|(idx, run)| {
@@ -165,36 +469,200 @@ impl State {
                 .collect(),
             Phase::RemoveRing => self
                 .board
-                .player_rings(self.current_player)
-                .map(|c| {
-                    Action::from(RemoveRing {
-                        player: self.current_player,
-                        coord: *c,
-                    })
-                })
+                .ring_coords(player)
+                .into_iter()
+                .map(|c| Action::from(RemoveRing { player, coord: c }))
                 .collect::<Vec<Action>>(),
-            Phase::PlayerWon(_) => Vec::new(),
+            Phase::PlayerWon(_) | Phase::Draw => Vec::new(),
+        }
+    }
+
+    // Executes the legal move selected by clicking `coord`, like the frontend does.
+    pub fn execute_for_coord(&mut self, coord: &HexCoord) -> Result<(), GameError> {
+        match self
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.primary_coord() == *coord)
+        {
+            Some(action) => self.apply_action(&action),
+            None => Err(self.click_error(coord)),
+        }
+    }
+
+    pub fn apply_action(&mut self, action: &Action) -> Result<(), GameError> {
+        if !action.is_legal(self) {
+            return Err(self.click_error(&action.coord()));
+        }
+        action.execute(self);
+        Ok(())
+    }
+
+    // Best explanation why nothing can be done at `coord` in the current phase.
+    fn click_error(&self, coord: &HexCoord) -> GameError {
+        let player = self.current_player;
+        match (self.current_phase, self.board.occupied(coord)) {
+            (Phase::PlayerWon(_) | Phase::Draw, _) => GameError::GameOver,
+            _ if !self.board.is_on_board(coord) => GameError::OffBoard(*coord),
+            (Phase::PlaceRing | Phase::MoveRing(_), Some(_)) => GameError::Occupied(*coord),
+            (Phase::PlaceMarker | Phase::RemoveRing, Some(piece))
+                if piece.is_ring() && !piece.owned_by(player) =>
+            {
+                GameError::NotYourPiece(*coord)
+            }
+            (Phase::RemoveRun, Some(piece)) if piece.is_marker() && !piece.owned_by(player) => {
+                GameError::NotYourPiece(*coord)
+            }
+            _ => GameError::NoSuchMove(*coord),
         }
     }
 
+    // Replays recorded clicks, failing with the index of the first click that did nothing.
+    pub fn play_clicks(&mut self, clicks: &[HexCoord]) -> Result<(), usize> {
+        for (idx, click) in clicks.iter().enumerate() {
+            if self.execute_for_coord(click).is_err() {
+                return Err(idx);
+            }
+        }
+        Ok(())
+    }
+
+    // A fresh state on the initial board with the whole history executed again.
+    pub fn replayed(&self) -> State {
+        self.replay_to(self.history.len())
+    }
+
+    // A fresh state on the initial board with the first `ply` actions executed.
+    pub fn replay_to(&self, ply: usize) -> State {
+        let mut state =
+            State::at_position(self.initial_board.clone(), self.first_player, self.initial_phase);
+        for action in self.history.iter().take(ply) {
+            action.execute(&mut state);
+        }
+        state
+    }
+
     pub fn next(&self, coord: HexCoord) {
         todo!();
     }
 
-    pub fn undo(&mut self) -> bool {
-        if let Some(m) = self.history.pop() {
-            m.undo(self);
-            return true;
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        let m = self.history.pop().ok_or(GameError::NoHistory)?;
+        m.undo(self);
+        Ok(())
+    }
+
+    // Make/unmake for search: recursing on a single State instead of cloning per
+    // node. Relies on every action's undo() exactly reversing its execute(),
+    // including the player and phase changes between the steps of a turn;
+    // only last_state_change is not restored. No legality check is done.
+    pub fn make(&mut self, action: &Action) {
+        action.execute(self);
+    }
+
+    pub fn unmake(&mut self) {
+        self.undo().expect("unmake without a matching make");
+    }
+
+    // RemoveRun without a run to remove only comes from an inconsistent history
+    // and would leave no legal moves. The turn then continues as if the runs had
+    // been removed. Returns whether the phase was changed.
+    pub fn recover_empty_remove_run(&mut self) -> bool {
+        if self.current_phase != Phase::RemoveRun || self.has_run(&self.current_player) {
+            return false;
+        }
+        self.compute_runs();
+        if self.has_run(&self.current_player) {
+            return false;
         }
-        false
+        if self.last_mover() != Some(self.current_player.other()) {
+            self.next_player();
+        }
+        if self.has_run(&self.current_player) {
+            self.set_phase(Phase::RemoveRun);
+        } else {
+            self.set_phase(Phase::PlaceMarker);
+        }
+        true
+    }
+
+    // Where the current player's ring at `from` could move, in any phase: for a
+    // ring still to be picked up as well as for the ring being moved. Empty for
+    // any other field.
+    pub fn ring_destinations(&self, from: &HexCoord) -> Vec<HexCoord> {
+        let own_ring = self.board.player_ring_at(from, &self.current_player);
+        if own_ring || self.current_phase == Phase::MoveRing(*from) {
+            self.board.ring_targets(from)
+        } else {
+            vec![]
+        }
+    }
+
+    // Takes back the whole last turn: the marker placement, the ring move and any
+    // run and ring removals it caused, or a single ring placement during setup.
+    pub fn undo_turn(&mut self) -> Result<(), GameError> {
+        if self.history.is_empty() {
+            return Err(GameError::NoHistory);
+        }
+        while let Some(action) = self.history.pop() {
+            action.undo(self);
+            if matches!(action, Action::PlaceMarker(_) | Action::PlaceRing(_)) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Player removing a run or a ring. After a move that completes runs for both
+    // players the mover resolves theirs first, then the opponent.
+    pub fn resolving_player(&self) -> Option<Player> {
+        matches!(self.current_phase, Phase::RemoveRun | Phase::RemoveRing)
+            .then_some(self.current_player)
+    }
+
+    // Player who made the most recent ring move, i.e. whose turn is being resolved.
+    pub fn last_mover(&self) -> Option<Player> {
+        self.history.iter().rev().find_map(|a| match a {
+            Action::MoveRing(m) => Some(m.player),
+            _ => None,
+        })
+    }
+
+    // Takes back the marker placed this turn while the ring has not been moved yet.
+    pub fn cancel_move_ring(&mut self) -> Result<(), GameError> {
+        if !matches!(self.current_phase, Phase::MoveRing(_)) {
+            return Err(GameError::WrongPhase(self.current_phase));
+        }
+        self.undo()
     }
 
     pub fn last_state_change(&self) -> Vec<StateChange> {
         self.last_state_change.clone()
     }
 
+    pub fn last_move_summary(&self) -> MoveSummary {
+        let mut summary = MoveSummary::default();
+        for change in &self.last_state_change {
+            match change {
+                StateChange::MarkerFlipped(_) => summary.flips += 1,
+                StateChange::RingPlaced(..) | StateChange::MarkerPlaced(..) => {
+                    summary.placements += 1
+                }
+                StateChange::MarkerRemoved(..) | StateChange::RingRemoved(..) => {
+                    summary.removals += 1
+                }
+                StateChange::RingMoved(..) => (),
+                StateChange::RunFormed(..) => summary.run_formed = true,
+            }
+        }
+        summary
+    }
+
     pub fn current_player_runs(&self) -> &Vec<Vec<HexCoord>> {
-        match self.current_player {
+        self.player_runs(self.current_player)
+    }
+
+    pub fn player_runs(&self, player: Player) -> &Vec<Vec<HexCoord>> {
+        match player {
             Player::Black => &self.runs_black,
             Player::White => &self.runs_white,
         }
@@ -206,6 +674,29 @@ impl State {
 
     pub fn set_phase(&mut self, phase: Phase) {
         self.current_phase = phase;
+        self.outcome = None;
+    }
+
+    // Ends the game outside of the rules of play, e.g. by resignation.
+    pub fn end(&mut self, result: GameResult) {
+        match result {
+            GameResult::White(_) => self.set_phase(Phase::PlayerWon(Player::White)),
+            GameResult::Black(_) => self.set_phase(Phase::PlayerWon(Player::Black)),
+            GameResult::Draw(_) => self.set_phase(Phase::Draw),
+            GameResult::Ongoing => return,
+        }
+        self.outcome = Some(result);
+    }
+
+    // A win without a recorded reason was played out by capturing rings; the
+    // rules never end in a draw on their own, so such a draw has no known reason.
+    pub fn result(&self) -> GameResult {
+        match (self.current_phase, self.outcome) {
+            (Phase::PlayerWon(_) | Phase::Draw, Some(result)) => result,
+            (Phase::PlayerWon(player), None) => GameResult::win(player, WinReason::RingsCaptured),
+            (Phase::Draw, None) => GameResult::Draw(DrawReason::Unknown),
+            _ => GameResult::Ongoing,
+        }
     }
 
     pub fn at_phase(&self, phase: &Phase) -> bool {
@@ -215,6 +706,26 @@ impl State {
     pub fn compute_runs(&mut self) {
         self.runs_white = self.board.runs(&Player::White);
         self.runs_black = self.board.runs(&Player::Black);
+        self.runs_white.sort();
+        self.runs_black.sort();
+    }
+
+    // Incremental compute_runs() for the case that only the fields in `changed`
+    // differ from the board the current runs were computed for.
+    pub fn update_runs_at(&mut self, changed: &[HexCoord]) {
+        for (player, runs) in [
+            (Player::White, &mut self.runs_white),
+            (Player::Black, &mut self.runs_black),
+        ] {
+            runs.retain(|r| !r.iter().any(|c| changed.contains(c)));
+            for c in changed {
+                for run in self.board.runs_through(c, &player) {
+                    if !runs.contains(&run) {
+                        runs.push(run);
+                    }
+                }
+            }
+        }
     }
 
     pub fn has_run(&self, player: &Player) -> bool {
@@ -259,6 +770,62 @@ impl State {
         }
     }
 
+    pub fn scores(&self) -> [(Player, usize); 2] {
+        Player::all().map(|p| (p, self.get_score(&p)))
+    }
+
+    // The player with more points, None on a tie.
+    pub fn leader(&self) -> Option<Player> {
+        match self.points_white.cmp(&self.points_black) {
+            std::cmp::Ordering::Greater => Some(Player::White),
+            std::cmp::Ordering::Less => Some(Player::Black),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.board.hash()
+            ^ zobrist::player_key(&self.current_player)
+            ^ zobrist::phase_key(&self.current_phase)
+            ^ zobrist::score_key(&Player::White, self.points_white)
+            ^ zobrist::score_key(&Player::Black, self.points_black)
+    }
+
+    pub fn record_position(&mut self) {
+        *self.position_counts.entry(self.hash()).or_insert(0) += 1;
+    }
+
+    pub fn forget_position(&mut self) {
+        let hash = self.hash();
+        if let Some(count) = self.position_counts.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.position_counts.remove(&hash);
+            }
+        }
+    }
+
+    // The current position occurred three times. Only offers a draw for rule
+    // variants that want one, the game does not end by itself.
+    pub fn is_threefold(&self) -> bool {
+        self.position_counts
+            .get(&self.hash())
+            .is_some_and(|n| *n >= 3)
+    }
+
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            census: self.board.census(),
+            board_hash: self.board.hash(),
+            phase: self.current_phase,
+            player: self.current_player,
+            points_white: self.points_white,
+            points_black: self.points_black,
+            runs_white: self.runs_white.iter().cloned().collect(),
+            runs_black: self.runs_black.iter().cloned().collect(),
+        }
+    }
+
     pub fn won_by(&self) -> Option<Player> {
         if let Phase::PlayerWon(player) = self.current_phase {
             return Some(player);
@@ -266,3 +833,530 @@ impl State {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::*;
+
+    use super::*;
+
+    fn sorted_runs(runs: &[Vec<HexCoord>]) -> Vec<Vec<(i8, i8)>> {
+        let mut runs: Vec<_> = runs
+            .iter()
+            .map(|r| r.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>())
+            .collect();
+        runs.sort();
+        runs
+    }
+
+    #[test]
+    fn test_incremental_runs_match_full_recompute() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut state = State::new(Board::new());
+        let coords = state.board.board_coords();
+
+        for _ in 0..2000 {
+            let c = *coords.choose(&mut rng).unwrap();
+            match rng.gen_range(0..4) {
+                0 => {
+                    state.board.remove(&c);
+                }
+                1 => {
+                    state.board.place_unchecked(&Piece::Marker(Player::Black), &c);
+                }
+                _ => {
+                    state.board.place_unchecked(&Piece::Marker(Player::White), &c);
+                }
+            }
+            state.update_runs_at(&[c]);
+
+            for player in [Player::White, Player::Black] {
+                assert_eq!(
+                    sorted_runs(state.player_runs(player)),
+                    sorted_runs(&state.board.runs(&player))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_from_initial_board() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut state = State::new(Board::new());
+
+        for _ in 0..400 {
+            if state.won_by().is_some() {
+                break;
+            }
+            let moves = state.legal_moves();
+            match moves.choose(&mut rng) {
+                Some(m) => m.execute(&mut state),
+                None => break,
+            }
+        }
+        assert!(state.history.len() > 20);
+
+        let replayed = state.replayed();
+        assert_eq!(replayed.snapshot().diff(&state.snapshot()), Vec::<String>::new());
+        assert_eq!(replayed.board.hash(), state.board.hash());
+        assert_eq!(replayed.initial_board.hash(), state.initial_board.hash());
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut state = State::new(Board::new());
+        let (a, b) = (HexCoord::new(0, 0), HexCoord::new(1, 1));
+        state.board.place_unchecked(&Piece::Ring(Player::White), &a);
+        state.set_phase(Phase::PlaceMarker);
+
+        // execute and undo leave the counts balanced
+        let action = state.legal_moves().remove(0);
+        action.execute(&mut state);
+        action.undo(&mut state);
+        assert!(state.position_counts.is_empty());
+
+        // a variant shuffling a ring back and forth
+        for i in 0..3 {
+            assert!(!state.is_threefold());
+            state.record_position();
+            state.board.remove(&a);
+            state.board.place_unchecked(&Piece::Ring(Player::White), &b);
+            state.record_position();
+            state.board.remove(&b);
+            state.board.place_unchecked(&Piece::Ring(Player::White), &a);
+            assert_eq!(state.is_threefold(), i == 2);
+        }
+    }
+
+    #[test]
+    fn test_play_clicks() {
+        let mut state = State::new(Board::new());
+        for i in -2..=1 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        let mut fresh = state.clone();
+
+        // rings alternating white and black, then white's marker and ring move
+        let clicks: Vec<HexCoord> = [
+            (2, 0),
+            (-3, -3),
+            (-3, 2),
+            (4, 1),
+            (3, 3),
+            (-4, -1),
+            (-1, 3),
+            (0, -4),
+            (1, -3),
+            (3, -1),
+            (2, 0),
+            (2, 1),
+        ]
+        .into_iter()
+        .map(HexCoord::from)
+        .collect();
+        assert_eq!(state.play_clicks(&clicks), Ok(()));
+        assert_eq!(state.current_phase, Phase::RemoveRun);
+        assert_eq!(state.runs_white.len(), 1);
+
+        // the second click hits the ring that was just placed
+        let bad = [HexCoord::new(1, 2), HexCoord::new(1, 2)];
+        assert_eq!(fresh.play_clicks(&bad), Err(1));
+    }
+
+    #[test]
+    fn test_snapshot_execute_undo() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &from);
+        for y in 1..5 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, y));
+        }
+        state.set_phase(Phase::PlaceMarker);
+        let before = state.snapshot();
+
+        let action = state.legal_moves().remove(0);
+        action.execute(&mut state);
+        assert!(!before.diff(&state.snapshot()).is_empty());
+        action.undo(&mut state);
+        assert_eq!(before.diff(&state.snapshot()), Vec::<String>::new());
+        assert_eq!(before, state.snapshot());
+
+        let mut moved = state.clone();
+        moved.points_black = 1;
+        assert_eq!(
+            before.diff(&moved.snapshot()),
+            vec!["black points: 0 != 1".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_phase_display() {
+        assert_eq!(Player::White.to_string(), "White");
+        assert_eq!(Player::Black.to_string(), "Black");
+
+        let white = Player::White;
+        assert_eq!(Phase::PlaceRing.describe(white), "Place ring (White to move)");
+        assert_eq!(
+            Phase::PlaceMarker.describe(white),
+            "Place marker (White to move)"
+        );
+        assert_eq!(
+            Phase::MoveRing(HexCoord::new(0, 0)).describe(Player::Black),
+            "Move ring from f6 (Black to move)"
+        );
+        assert_eq!(Phase::RemoveRun.describe(white), "Remove run (White to move)");
+        assert_eq!(Phase::RemoveRing.describe(white), "Remove ring (White to move)");
+        assert_eq!(Phase::PlayerWon(Player::Black).describe(white), "Black won");
+        assert_eq!(Phase::Draw.describe(white), "Draw");
+        assert_eq!(Phase::PlaceMarker.to_string(), "Place marker");
+    }
+
+    #[test]
+    fn test_legal_moves_for() {
+        let mut state = State::new(Board::new());
+        let white = [HexCoord::new(1, 2), HexCoord::new(0, 1)];
+        let black = [HexCoord::new(-2, -1)];
+        for c in &white {
+            state.board.place_unchecked(&Piece::Ring(Player::White), c);
+        }
+        for c in &black {
+            state.board.place_unchecked(&Piece::Ring(Player::Black), c);
+        }
+        state.set_phase(Phase::PlaceMarker);
+
+        let coords = |moves: Vec<Action>| moves.iter().map(|a| a.coord()).collect::<Vec<_>>();
+        assert_eq!(
+            coords(state.legal_moves_for(Player::White)),
+            coords(state.legal_moves())
+        );
+        assert_eq!(coords(state.legal_moves_for(Player::Black)), black.to_vec());
+
+        Action::from(PlaceMarker { coord: white[0] }).execute(&mut state);
+        assert_eq!(state.current_phase, Phase::MoveRing(white[0]));
+        assert_eq!(coords(state.legal_moves_for(Player::Black)), black.to_vec());
+        assert_eq!(
+            state.legal_moves_for(Player::White).len(),
+            state.board.ring_targets(&white[0]).len()
+        );
+    }
+
+    #[test]
+    fn test_grouped_legal_moves() {
+        let mut state = State::new(Board::new());
+        for y in -2..3 {
+            for x in [-1, 2] {
+                state
+                    .board
+                    .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(x, y));
+            }
+        }
+        state.compute_runs();
+        state.set_phase(Phase::RemoveRun);
+
+        let groups = state.grouped_legal_moves();
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].0.starts_with("Remove run A"));
+        assert!(groups[1].0.starts_with("Remove run B"));
+        assert!(groups.iter().all(|(_, moves)| moves.len() == 1));
+
+        let state = State::new(Board::new());
+        let groups = state.grouped_legal_moves();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1.len(), state.board.board_coords().len());
+    }
+
+    #[test]
+    fn test_leader() {
+        let mut state = State::new(Board::new());
+        assert_eq!(state.leader(), None);
+
+        state.points_white = 2;
+        state.points_black = 1;
+        assert_eq!(state.leader(), Some(Player::White));
+        assert_eq!(state.scores(), [(Player::White, 2), (Player::Black, 1)]);
+
+        state.points_black = 3;
+        assert_eq!(state.leader(), Some(Player::Black));
+
+        state.points_white = 3;
+        assert_eq!(state.leader(), None);
+    }
+
+    #[test]
+    fn test_click_errors() {
+        let mut state = State::new(Board::new());
+        let (white, black) = (HexCoord::new(0, 0), HexCoord::new(1, 1));
+        assert_eq!(state.undo(), Err(GameError::NoHistory));
+        assert_eq!(state.execute_for_coord(&white), Ok(()));
+        assert_eq!(state.execute_for_coord(&white), Err(GameError::Occupied(white)));
+        assert_eq!(
+            state.execute_for_coord(&HexCoord::new(0, 6)),
+            Err(GameError::OffBoard(HexCoord::new(0, 6)))
+        );
+        state.execute_for_coord(&black).unwrap();
+
+        state.set_phase(Phase::PlaceMarker);
+        assert_eq!(state.execute_for_coord(&black), Err(GameError::NotYourPiece(black)));
+        let empty = HexCoord::new(2, 0);
+        assert_eq!(state.execute_for_coord(&empty), Err(GameError::NoSuchMove(empty)));
+
+        state.execute_for_coord(&white).unwrap();
+        assert_eq!(state.execute_for_coord(&black), Err(GameError::Occupied(black)));
+
+        state.set_phase(Phase::PlayerWon(Player::White));
+        assert_eq!(state.execute_for_coord(&empty), Err(GameError::GameOver));
+        let action = Action::from(PlaceMarker { coord: white });
+        assert_eq!(state.apply_action(&action), Err(GameError::GameOver));
+    }
+
+    #[test]
+    fn test_cancel_move_ring() {
+        let mut state = State::new(Board::new());
+        let c = HexCoord::new(1, 2);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &c);
+        state.set_phase(Phase::PlaceMarker);
+
+        assert_eq!(
+            state.cancel_move_ring(),
+            Err(GameError::WrongPhase(Phase::PlaceMarker))
+        );
+
+        let action = Action::from(PlaceMarker { coord: c });
+        action.execute(&mut state);
+        assert_eq!(state.current_phase, Phase::MoveRing(c));
+
+        assert_eq!(state.cancel_move_ring(), Ok(()));
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.current_player, Player::White);
+        assert!(state.board.player_ring_at(&c, &Player::White));
+        assert_eq!(state.board.markers().count(), 0);
+        assert!(state.history.is_empty());
+    }
+
+    #[test]
+    fn test_last_move_summary() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(-2, -1);
+        state.set_phase(Phase::MoveRing(from));
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 0));
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 1));
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(-2, 2));
+
+        let action = Action::from(MoveRing {
+            player: Player::White,
+            from,
+            to: HexCoord::new(-2, 3),
+        });
+        action.execute(&mut state);
+
+        let summary = state.last_move_summary();
+        assert_eq!(summary.flips, 3);
+        assert_eq!(summary.placements, 0);
+        assert_eq!(summary.removals, 0);
+        assert!(!summary.run_formed);
+        assert_eq!(summary.to_string(), "flipped 3 markers");
+    }
+
+    #[test]
+    fn test_last_move_summary_run() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(-2, -1);
+        state.set_phase(Phase::MoveRing(from));
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-2, 0));
+        for i in -1..=2 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        let action = Action::from(MoveRing {
+            player: Player::White,
+            from,
+            to: HexCoord::new(-2, 1),
+        });
+        action.execute(&mut state);
+
+        let summary = state.last_move_summary();
+        assert_eq!(summary.flips, 1);
+        assert!(summary.run_formed);
+        assert_eq!(summary.to_string(), "flipped 1 marker, formed a run");
+    }
+
+    // White to place a marker at (2, 0) and move the ring north, completing a
+    // run on the x axis.
+    fn run_position() -> State {
+        let mut state = State::new(Board::new());
+        for i in -2..=1 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(i, 0));
+        }
+        for (player, c) in [
+            (Player::White, HexCoord::new(2, 0)),
+            (Player::White, HexCoord::new(-3, 2)),
+            (Player::Black, HexCoord::new(-3, -3)),
+        ] {
+            state.board.place_unchecked(&Piece::Ring(player), &c);
+        }
+        state.set_phase(Phase::PlaceMarker);
+        state
+    }
+
+    #[test]
+    fn test_undo_turn_reverts_run_removal() {
+        let mut state = run_position();
+        let before = state.board.hash();
+
+        for c in [(2, 0), (2, 1), (-2, 0), (2, 1)] {
+            state.execute_for_coord(&HexCoord::from(c)).unwrap();
+        }
+        assert_eq!(state.history.len(), 4);
+        assert_eq!(state.points_white, 1);
+        assert_eq!(state.current_player, Player::Black);
+
+        state.undo_turn().unwrap();
+        assert!(state.history.is_empty());
+        assert_eq!(state.board.hash(), before);
+        assert_eq!(state.current_player, Player::White);
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.points_white, 0);
+        assert_eq!(state.undo_turn(), Err(GameError::NoHistory));
+    }
+
+    #[test]
+    fn test_legal_moves_json() {
+        let empty = State::new(Board::new());
+        let json = empty.legal_moves_json();
+        assert!(json.starts_with(r#"[{"type":"place_ring","coord":["#));
+        assert_eq!(json.matches("place_ring").count(), empty.legal_moves().len());
+
+        let mut state = run_position();
+        assert_eq!(
+            state.legal_moves_json(),
+            r#"[{"type":"place_marker","coord":[-3,2]},{"type":"place_marker","coord":[2,0]}]"#
+        );
+
+        state.execute_for_coord(&HexCoord::new(2, 0)).unwrap();
+        let json = state.legal_moves_json();
+        assert!(json.contains(r#"{"type":"move_ring","from":[2,0],"to":[2,1],"flips":0}"#));
+        assert!(json.contains(r#"{"type":"move_ring","from":[2,0],"to":[-3,0],"flips":4}"#));
+
+        state.execute_for_coord(&HexCoord::new(2, 1)).unwrap();
+        assert_eq!(
+            state.legal_moves_json(),
+            r#"[{"type":"remove_run","coord":[-2,0],"run":[[-2,0],[-1,0],[0,0],[1,0],[2,0]]}]"#
+        );
+
+        state.execute_for_coord(&HexCoord::new(-2, 0)).unwrap();
+        let json = state.legal_moves_json();
+        assert!(json.contains(r#"{"type":"remove_ring","coord":[2,1]}"#));
+        assert!(json.contains(r#"{"type":"remove_ring","coord":[-3,2]}"#));
+    }
+
+    #[test]
+    fn test_result() {
+        let mut state = run_position();
+        assert_eq!(state.result(), GameResult::Ongoing);
+        state.points_white = 2;
+        for c in [(2, 0), (2, 1), (-2, 0), (2, 1)] {
+            state.execute_for_coord(&HexCoord::from(c)).unwrap();
+        }
+        assert_eq!(state.result(), GameResult::White(WinReason::RingsCaptured));
+
+        let mut state = run_position();
+        state.end(GameResult::Black(WinReason::Resignation));
+        assert_eq!(state.won_by(), Some(Player::Black));
+        assert_eq!(state.result(), GameResult::Black(WinReason::Resignation));
+        // play resumed, e.g. after undo, forgets the reason
+        state.set_phase(Phase::PlaceMarker);
+        assert_eq!(state.result(), GameResult::Ongoing);
+
+        state.end(GameResult::Draw(DrawReason::Repetition));
+        assert_eq!(state.current_phase, Phase::Draw);
+        assert_eq!(state.result(), GameResult::Draw(DrawReason::Repetition));
+        state.set_phase(Phase::Draw);
+        assert_eq!(state.result(), GameResult::Draw(DrawReason::Unknown));
+    }
+
+    fn make_unmake_dfs(state: &mut State, depth: u32) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 1;
+        for action in state.legal_moves() {
+            let before = state.snapshot();
+            state.make(&action);
+            nodes += make_unmake_dfs(state, depth - 1);
+            state.unmake();
+            assert_eq!(before.diff(&state.snapshot()), Vec::<String>::new(), "{:?}", action);
+        }
+        nodes
+    }
+
+    #[test]
+    fn test_make_unmake_restores_root() {
+        // covers marker placement, ring moves, run and ring removal
+        let mut state = run_position();
+        let root = state.snapshot();
+        assert!(make_unmake_dfs(&mut state, 4) > 100);
+        assert_eq!(state.snapshot(), root);
+        assert!(state.history.is_empty());
+
+        let mut state = State::new(Board::new());
+        let root = state.snapshot();
+        make_unmake_dfs(&mut state, 2);
+        assert_eq!(state.snapshot(), root);
+    }
+
+    #[test]
+    fn test_ring_destinations() {
+        let mut state = run_position();
+        let ring = HexCoord::new(2, 0);
+        assert_eq!(
+            state.ring_destinations(&ring),
+            state.board.ring_targets(&ring)
+        );
+        assert!(!state.ring_destinations(&ring).is_empty());
+        assert!(state.ring_destinations(&HexCoord::new(0, 3)).is_empty());
+        assert!(state.ring_destinations(&HexCoord::new(-3, -3)).is_empty());
+
+        state.execute_for_coord(&ring).unwrap();
+        assert_eq!(
+            state.ring_destinations(&ring),
+            state.board.ring_targets(&ring)
+        );
+    }
+
+    #[test]
+    fn test_empty_remove_run_recovers() {
+        let mut state = run_position();
+        state.set_phase(Phase::RemoveRun);
+        assert!(state.legal_moves().is_empty());
+
+        assert!(state.recover_empty_remove_run());
+        assert_eq!(state.current_phase, Phase::PlaceMarker);
+        assert_eq!(state.current_player, Player::Black);
+        assert!(!state.legal_moves().is_empty());
+        assert!(!state.recover_empty_remove_run());
+
+        // runs that are on the board but missing from the cache are kept
+        let mut state = run_position();
+        state
+            .board
+            .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(-3, 0));
+        state.set_phase(Phase::RemoveRun);
+        assert!(!state.recover_empty_remove_run());
+        assert_eq!(state.player_runs(Player::White).len(), 1);
+    }
+}
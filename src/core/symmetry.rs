@@ -0,0 +1,206 @@
+use crate::common::coord::*;
+use crate::core::actions::*;
+use crate::core::board::Board;
+use crate::core::state::*;
+
+// Smallest coord of the orbit of `c` under the board symmetries.
+fn orbit_representative(c: &HexCoord) -> HexCoord {
+    (0..N_SYMMETRIES).map(|sym| c.transform(sym)).min().unwrap()
+}
+
+impl Board {
+    pub fn is_symmetric_under(&self, sym: u8) -> bool {
+        self.iter_pieces()
+            .all(|(c, p)| self.occupied(&c.transform(sym)) == Some(&p))
+    }
+
+    // Id of the orbit of the field `c` under rotations and reflections; fields
+    // that map onto each other share it. Ids count up from the orbit of the
+    // smallest coord, so they only depend on the board size. None off the board.
+    pub fn symmetry_class(&self, c: &HexCoord) -> Option<u8> {
+        if !self.is_on_board(c) {
+            return None;
+        }
+        let mut reps: Vec<HexCoord> = self
+            .board_coords()
+            .iter()
+            .map(orbit_representative)
+            .collect();
+        reps.sort();
+        reps.dedup();
+        let rep = orbit_representative(c);
+        reps.iter().position(|r| *r == rep).map(|idx| idx as u8)
+    }
+}
+
+impl Action {
+    pub fn transform(&self, sym: u8) -> Action {
+        match self {
+            Action::PlaceRing(a) => Action::from(PlaceRing {
+                coord: a.coord.transform(sym),
+            }),
+            Action::PlaceMarker(a) => Action::from(PlaceMarker {
+                coord: a.coord.transform(sym),
+            }),
+            Action::MoveRing(a) => Action::from(MoveRing {
+                player: a.player,
+                from: a.from.transform(sym),
+                to: a.to.transform(sym),
+            }),
+            Action::RemoveRun(a) => {
+                let run: Vec<HexCoord> = a.run.iter().map(|c| c.transform(sym)).collect();
+                Action::from(RemoveRun {
+                    run_idx: a.run_idx,
+                    coord: run[0],
+                    run,
+                })
+            }
+            Action::RemoveRing(a) => Action::from(RemoveRing {
+                player: a.player,
+                coord: a.coord.transform(sym),
+            }),
+        }
+    }
+
+    // coordinates touched by the action, order independent
+    fn symmetry_key(&self) -> Vec<HexCoord> {
+        let mut key = match self {
+            Action::PlaceRing(a) => vec![a.coord],
+            Action::PlaceMarker(a) => vec![a.coord],
+            Action::MoveRing(a) => vec![a.from, a.to],
+            Action::RemoveRun(a) => a.run.clone(),
+            Action::RemoveRing(a) => vec![a.coord],
+        };
+        key.sort_by_key(|c| (c.0, c.1));
+        key
+    }
+}
+
+// Symmetries mapping the position onto itself.
+pub fn stabilizer(state: &State) -> Vec<u8> {
+    (0..N_SYMMETRIES)
+        .filter(|&sym| match state.current_phase {
+            Phase::MoveRing(from) => from.transform(sym) == from,
+            _ => true,
+        })
+        .filter(|&sym| state.board.is_symmetric_under(sym))
+        .collect()
+}
+
+// Keeps one representative of every class of moves that lead to symmetric positions.
+pub fn dedup_symmetric_moves(state: &State, moves: Vec<Action>) -> Vec<Action> {
+    let syms = stabilizer(state);
+    let mut seen = vec![];
+    let mut res = vec![];
+    for m in moves {
+        let key = m.symmetry_key();
+        if seen.contains(&key) {
+            continue;
+        }
+        for &sym in &syms {
+            seen.push(m.transform(sym).symmetry_key());
+        }
+        seen.push(key);
+        res.push(m);
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::core::ai::RandomAI;
+    use crate::core::command::Command;
+    use crate::core::entities::*;
+
+    use super::*;
+
+    #[test]
+    fn test_symmetry_classes() {
+        let board = Board::new();
+        let center = HexCoord::new(0, 0);
+        let class_of = |c: &HexCoord| board.symmetry_class(c).unwrap();
+        let coords = board.board_coords();
+        assert!(coords
+            .iter()
+            .all(|c| *c == center || class_of(c) != class_of(&center)));
+
+        let corners: Vec<HexCoord> = (0..6)
+            .map(|sym| HexCoord::new(0, 4).transform(sym))
+            .collect();
+        assert_eq!(corners.iter().collect::<HashSet<_>>().len(), 6);
+        assert!(corners.iter().all(|c| class_of(c) == class_of(&corners[0])));
+        assert_eq!(
+            coords.iter().filter(|c| class_of(c) == class_of(&corners[0])).count(),
+            6
+        );
+
+        for c in &coords {
+            for sym in 0..N_SYMMETRIES {
+                assert_eq!(class_of(&c.transform(sym)), class_of(c));
+            }
+        }
+        let classes: HashSet<u8> = coords.iter().map(class_of).collect();
+        assert_eq!(classes.len(), 11);
+        assert_eq!(board.symmetry_class(&HexCoord::new(5, 5)), None);
+        assert_eq!(board.symmetry_class(&HexCoord::new(0, 6)), None);
+    }
+
+    #[test]
+    fn test_dedup_empty_board() {
+        let state = State::new(Board::new());
+        let moves = state.legal_moves();
+        assert_eq!(moves.len(), state.board.board_coords().len());
+
+        let reps = dedup_symmetric_moves(&state, moves);
+        assert!(reps.len() * 6 < state.board.board_coords().len());
+
+        // every field is equivalent to exactly one representative
+        for c in state.board.board_coords() {
+            let orbit: HashSet<HexCoord> = (0..N_SYMMETRIES).map(|s| c.transform(s)).collect();
+            assert_eq!(
+                reps.iter().filter(|m| orbit.contains(&m.coord())).count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn test_ai_candidate_moves() {
+        let state = State::new(Board::new());
+        let ai = RandomAI::new(Player::White, 1);
+        assert_eq!(ai.candidate_moves(&state).len(), 85);
+        let ai = ai.with_symmetry_dedup(true);
+        assert!(ai.candidate_moves(&state).len() < 85 / 6);
+    }
+
+    #[test]
+    fn test_dedup_asymmetric_board_keeps_all() {
+        let mut state = State::new(Board::new());
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(1, 3));
+        let moves = state.legal_moves();
+        let n = moves.len();
+        assert_eq!(stabilizer(&state), vec![0]);
+        assert_eq!(dedup_symmetric_moves(&state, moves).len(), n);
+    }
+
+    #[test]
+    fn test_dedup_mirror_symmetric_board() {
+        let mut state = State::new(Board::new());
+        // the NE axis through the origin is a mirror axis
+        state
+            .board
+            .place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(2, 2));
+        assert_eq!(stabilizer(&state), vec![0, 6]);
+
+        let reps = dedup_symmetric_moves(&state, state.legal_moves());
+        assert!(reps.len() < state.legal_moves().len());
+        let a = Action::from(PlaceRing {
+            coord: HexCoord::new(1, 3),
+        });
+        assert_eq!(a.transform(6).coord(), HexCoord::new(3, 1));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::core::ai::Ai;
+use crate::core::board::SetupError;
+use crate::core::config::GameConfig;
+use crate::core::entities::Player;
+use crate::core::selfplay::self_play;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    pub wins_a: usize,
+    pub wins_b: usize,
+    pub draws: usize,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> usize {
+        self.wins_a + self.wins_b + self.draws
+    }
+}
+
+// Called after every game with the running result, returning true ends the
+// match early, e.g. once an SPRT bound is crossed.
+pub type EarlyStop = Box<dyn FnMut(&MatchResult) -> bool>;
+
+// Plays a series of games between two AIs. `a` has White in even games and
+// Black in odd ones.
+pub struct MatchRunner {
+    games: usize,
+    config: GameConfig,
+    seed: u64,
+    early_stop: Option<EarlyStop>,
+}
+
+impl MatchRunner {
+    pub fn new(games: usize, config: GameConfig) -> Self {
+        MatchRunner {
+            games,
+            config,
+            seed: 0,
+            early_stop: None,
+        }
+    }
+
+    // Game i is played with seed `seed + i`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_early_stop(mut self, early_stop: EarlyStop) -> Self {
+        self.early_stop = Some(early_stop);
+        self
+    }
+
+    pub fn run(&mut self, a: &mut dyn Ai, b: &mut dyn Ai) -> Result<MatchResult, SetupError> {
+        let mut result = MatchResult::default();
+        for i in 0..self.games {
+            let a_player = if i % 2 == 0 {
+                Player::White
+            } else {
+                Player::Black
+            };
+            a.set_player(a_player);
+            b.set_player(a_player.other());
+
+            let seed = self.seed.wrapping_add(i as u64);
+            let game = match a_player {
+                Player::White => self_play(a, b, self.config, seed)?,
+                Player::Black => self_play(b, a, self.config, seed)?,
+            };
+            match game.winner {
+                Some(p) if p == a_player => result.wins_a += 1,
+                Some(_) => result.wins_b += 1,
+                None => result.draws += 1,
+            }
+
+            if let Some(early_stop) = self.early_stop.as_mut() {
+                if early_stop(&result) {
+                    break;
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::ai::RandomAI;
+
+    use super::*;
+
+    fn short_games() -> GameConfig {
+        GameConfig {
+            max_plies: 30,
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_match_counts_all_games() {
+        let mut a = RandomAI::new(Player::White, 1);
+        let mut b = RandomAI::new(Player::Black, 1);
+        let result = MatchRunner::new(4, short_games())
+            .with_seed(5)
+            .run(&mut a, &mut b)
+            .unwrap();
+        assert_eq!(result.games(), 4);
+    }
+
+    #[test]
+    fn test_early_stop() {
+        let mut a = RandomAI::new(Player::White, 1);
+        let mut b = RandomAI::new(Player::Black, 1);
+        let result = MatchRunner::new(4, short_games())
+            .with_early_stop(Box::new(|r| r.games() >= 2))
+            .run(&mut a, &mut b)
+            .unwrap();
+        assert_eq!(result.games(), 2);
+    }
+}
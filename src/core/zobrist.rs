@@ -0,0 +1,53 @@
+use crate::common::coord::HexCoord;
+use crate::core::entities::*;
+use crate::core::state::Phase;
+
+// Keys are derived on the fly from a fixed seed instead of a random table, so
+// hashes are stable across runs and can be stored (e.g. in an opening book).
+const SEED: u64 = 0x5eed_9e37_79b9_7f4a;
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn key(tag: u64, a: i8, b: i8, c: u8) -> u64 {
+    splitmix64(SEED ^ (tag << 32) ^ ((a as u8 as u64) << 16) ^ ((b as u8 as u64) << 8) ^ c as u64)
+}
+
+fn player_idx(player: &Player) -> u8 {
+    match player {
+        Player::White => 0,
+        Player::Black => 1,
+    }
+}
+
+pub fn piece_key(coord: &HexCoord, piece: &Piece) -> u64 {
+    let kind = match piece {
+        Piece::Ring(p) => player_idx(p),
+        Piece::Marker(p) => 2 + player_idx(p),
+    };
+    key(1, coord.0, coord.1, kind)
+}
+
+pub fn player_key(player: &Player) -> u64 {
+    key(2, 0, 0, player_idx(player))
+}
+
+pub fn phase_key(phase: &Phase) -> u64 {
+    match phase {
+        Phase::PlaceRing => key(3, 0, 0, 0),
+        Phase::PlaceMarker => key(3, 0, 0, 1),
+        Phase::MoveRing(from) => key(3, from.0, from.1, 2),
+        Phase::RemoveRun => key(3, 0, 0, 3),
+        Phase::RemoveRing => key(3, 0, 0, 4),
+        Phase::PlayerWon(p) => key(3, 0, 0, 5 + player_idx(p)),
+        Phase::Draw => key(3, 0, 0, 7),
+    }
+}
+
+pub fn score_key(player: &Player, score: usize) -> u64 {
+    key(4, score as i8, 0, player_idx(player))
+}
@@ -5,15 +5,52 @@ use macroquad::prelude::*;
 
 use super::elements::token::{Token, TokenType};
 
+// Wall clock in seconds. Unlike get_time() this does not need a macroquad
+// context, so animations can be created and ticked headless.
+pub fn now() -> f64 {
+    macroquad::miniquad::date::now()
+}
+
+// Animations are driven by the frame time passed to tick() and start with
+// their first tick, so they don't skip ahead when a frame is late.
 pub trait Animation {
-    fn tick(&mut self);
+    fn tick(&mut self, time: f64);
     fn finished(&self) -> bool;
     fn apply(&self, marker: &mut Token);
 }
 
+// Time since the first tick.
+#[derive(Clone, Copy, Debug, Default)]
+struct Elapsed {
+    start: Option<f64>,
+    seconds: f64,
+}
+
+impl Elapsed {
+    fn tick(&mut self, time: f64) -> f64 {
+        let start = *self.start.get_or_insert(time);
+        self.seconds = time - start;
+        self.seconds
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationConfig {
+    // scale-in of newly placed rings and markers and the turn change sweep on
+    // the scoreboard; moves, flips and removals always animate
+    pub enabled: bool,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        AnimationConfig { enabled: true }
+    }
+}
+
 #[derive(Clone)]
 pub struct FlipAnimation {
-    start_time: f64,
+    elapsed: Elapsed,
+    delay: f64,
     duration: f64,
     start_color: Color,
     end_color: Color,
@@ -23,7 +60,8 @@ pub struct FlipAnimation {
 impl FlipAnimation {
     pub fn new(start_color: Color, end_color: Color) -> Self {
         FlipAnimation {
-            start_time: get_time(),
+            elapsed: Elapsed::default(),
+            delay: 0.,
             duration: 0.2,
             start_color,
             end_color,
@@ -34,11 +72,18 @@ impl FlipAnimation {
     pub fn new_box(start_color: Color, end_color: Color) -> Box<Self> {
         Box::new(Self::new(start_color, end_color))
     }
+
+    // Keeps the start color for `delay` seconds before flipping.
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.delay = delay;
+        self
+    }
 }
 
 impl Animation for FlipAnimation {
-    fn tick(&mut self) {
-        let delta = (1. / self.duration * (get_time() - self.start_time)) as f32;
+    fn tick(&mut self, time: f64) {
+        let t = self.elapsed.tick(time) - self.delay;
+        let delta = ((1. / self.duration * t) as f32).clamp(0., 1.);
         self.current_color = Color::from_vec(
             self.start_color.to_vec()
                 + delta * (self.end_color.to_vec() - self.start_color.to_vec()),
@@ -50,13 +95,13 @@ impl Animation for FlipAnimation {
     }
 
     fn finished(&self) -> bool {
-        get_time() - self.start_time > self.duration
+        self.elapsed.seconds - self.delay > self.duration
     }
 }
 
 #[derive(Clone)]
 pub struct RemoveAnimation {
-    start_time: f64,
+    elapsed: Elapsed,
     duration: f64,
     amplitude: f32,
     phase_shift: f32,
@@ -68,7 +113,7 @@ impl RemoveAnimation {
         let phase_shift = (1. / expand_ratio).asin();
 
         RemoveAnimation {
-            start_time: get_time(),
+            elapsed: Elapsed::default(),
             duration: 0.2,
             phase_shift,
             amplitude: expand_ratio,
@@ -82,8 +127,8 @@ impl RemoveAnimation {
 }
 
 impl Animation for RemoveAnimation {
-    fn tick(&mut self) {
-        let t = (1. / self.duration * (get_time() - self.start_time)) as f32;
+    fn tick(&mut self, time: f64) {
+        let t = (1. / self.duration * self.elapsed.tick(time)) as f32;
         let delta = self.phase_shift + t * (PI - self.phase_shift);
         self.value = self.amplitude * delta.sin();
     }
@@ -98,13 +143,13 @@ impl Animation for RemoveAnimation {
     }
 
     fn finished(&self) -> bool {
-        get_time() - self.start_time > self.duration
+        self.elapsed.seconds > self.duration
     }
 }
 
 #[derive(Clone)]
 pub struct MoveAnimation {
-    start_time: f64,
+    elapsed: Elapsed,
     duration: f64,
     start_pos: Point,
     end_pos: Point,
@@ -114,7 +159,7 @@ pub struct MoveAnimation {
 impl MoveAnimation {
     pub fn new(start_pos: Point, end_pos: Point) -> Self {
         MoveAnimation {
-            start_time: get_time(),
+            elapsed: Elapsed::default(),
             duration: 0.5,
             start_pos,
             end_pos,
@@ -128,13 +173,9 @@ impl MoveAnimation {
 }
 
 impl Animation for MoveAnimation {
-    fn tick(&mut self) {
-        if self.finished() {
-            self.current_pos = self.end_pos;
-        } else {
-            let delta = (1. / self.duration * (get_time() - self.start_time)) as f32;
-            self.current_pos = self.start_pos + (self.end_pos - self.start_pos) * delta;
-        }
+    fn tick(&mut self, time: f64) {
+        let delta = (self.elapsed.tick(time) / self.duration).min(1.) as f32;
+        self.current_pos = Point::lerp(self.start_pos, self.end_pos, delta);
     }
 
     fn apply(&self, ring: &mut Token) {
@@ -142,6 +183,141 @@ impl Animation for MoveAnimation {
     }
 
     fn finished(&self) -> bool {
-        get_time() - self.start_time >= self.duration
+        self.elapsed.seconds >= self.duration
+    }
+}
+
+// Grows a placed piece from nothing to its nominal size, with a slight ease-out.
+#[derive(Clone)]
+pub struct PlaceAnimation {
+    elapsed: Elapsed,
+    duration: f64,
+    nominal: TokenType,
+    scale: f32,
+}
+
+impl PlaceAnimation {
+    pub fn new(nominal: TokenType) -> Self {
+        PlaceAnimation {
+            elapsed: Elapsed::default(),
+            duration: 0.15,
+            nominal,
+            scale: 0.,
+        }
+    }
+
+    pub fn new_box(nominal: TokenType) -> Box<Self> {
+        Box::new(Self::new(nominal))
+    }
+}
+
+impl Animation for PlaceAnimation {
+    fn tick(&mut self, time: f64) {
+        let t = (self.elapsed.tick(time) / self.duration).clamp(0., 1.) as f32;
+        self.scale = 1. - (1. - t).powi(2);
+    }
+
+    fn apply(&self, token: &mut Token) {
+        token.shape_type = match self.nominal {
+            TokenType::Ring(r1, r2) => TokenType::Ring(self.scale * r1, self.scale * r2),
+            TokenType::Marker(r) => TokenType::Marker(self.scale * r),
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed.seconds >= self.duration
+    }
+}
+
+// Sweep over the scoreboard row of the player who just got the turn. It
+// doesn't change a token, so it is read via progress() instead of apply().
+#[derive(Clone, Copy, Debug)]
+pub struct TurnChangeAnimation {
+    elapsed: Elapsed,
+    duration: f64,
+}
+
+impl TurnChangeAnimation {
+    pub fn new() -> Self {
+        TurnChangeAnimation {
+            elapsed: Elapsed::default(),
+            duration: 0.6,
+        }
+    }
+
+    pub fn tick(&mut self, time: f64) {
+        self.elapsed.tick(time);
+    }
+
+    // 0 when started, 1 when finished.
+    pub fn progress(&self) -> f32 {
+        (self.elapsed.seconds / self.duration).clamp(0., 1.) as f32
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed.seconds >= self.duration
+    }
+}
+
+impl Default for TurnChangeAnimation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::entities::Player;
+
+    use super::*;
+
+    fn marker_radius(token: &Token) -> f32 {
+        match token.shape_type {
+            TokenType::Marker(r) => r,
+            TokenType::Ring(..) => panic!("expected a marker"),
+        }
+    }
+
+    #[test]
+    fn test_place_animation_grows() {
+        let mut token = Token::new_marker_at_point(Point(0., 0.), Player::White, 1);
+        let nominal = marker_radius(&token);
+        let mut animation = PlaceAnimation::new(token.shape_type.clone());
+
+        // starts with the first tick, whatever the clock says
+        animation.tick(100.);
+        animation.apply(&mut token);
+        let first = marker_radius(&token);
+        assert!(first < 0.5 * nominal);
+
+        animation.tick(100.06);
+        animation.apply(&mut token);
+        let middle = marker_radius(&token);
+        assert!(first < middle && middle < nominal);
+        assert!(!animation.finished());
+
+        animation.tick(100.18);
+        animation.apply(&mut token);
+        assert!(animation.finished());
+        assert_eq!(marker_radius(&token), nominal);
+    }
+
+    #[test]
+    fn test_flip_waits_for_delay() {
+        let mut token = Token::new_marker_at_point(Point(0., 0.), Player::White, 1);
+        let mut animation = FlipAnimation::new(WHITE, BLACK).with_delay(0.1);
+        for time in [0., 0.1] {
+            animation.tick(time);
+            animation.apply(&mut token);
+            assert_eq!(token.color(), WHITE);
+        }
+        animation.tick(0.2);
+        animation.apply(&mut token);
+        assert!(token.color().r < WHITE.r && token.color().r > BLACK.r);
+        assert!(!animation.finished());
+        animation.tick(0.31);
+        animation.apply(&mut token);
+        assert!(animation.finished());
+        assert_eq!(token.color(), BLACK);
     }
 }
@@ -17,6 +17,8 @@ pub struct Controller {
     subscribers: HashMap<ElementId, Vec<ElementId>>,
     actions: Vec<UiAction>,
     events: Vec<Event>,
+    // ids are handed out in insertion order and never reused
+    next_id: ElementId,
 }
 
 fn insert_hashmap_vec<K, V>(hashmap: &mut HashMap<K, Vec<V>>, key: K, value: V)
@@ -35,6 +37,7 @@ impl Controller {
             subscribers: HashMap::new(),
             actions: vec![],
             events: vec![],
+            next_id: 0,
         }
     }
 
@@ -45,7 +48,8 @@ impl Controller {
     }
 
     pub fn add_element_inactive(&mut self, element: Box<dyn Element>) -> ElementId {
-        let id = self.elements.len();
+        let id = self.next_id;
+        self.next_id += 1;
         self.elements.insert(id, element);
         id
     }
@@ -54,6 +58,18 @@ impl Controller {
         self.actions.clone()
     }
 
+    pub fn animations_pending(&self) -> bool {
+        self.elements.values().any(|e| e.is_animated())
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn has_pending_events(&self) -> bool {
+        !self.events.is_empty()
+    }
+
     pub fn clear_all(&mut self) {
         self.elements.clear();
         self.messages.clear();
@@ -108,16 +124,39 @@ impl Controller {
         });
     }
 
+    // Called every frame after rendering; animations don't depend on input
+    // events to progress. Resulting actions are reported with the frame's.
+    pub fn tick_animations(&mut self, time: f64) {
+        for element in self.elements.values_mut() {
+            if let Some(action) = element.tick(time) {
+                self.actions.push(action);
+            }
+        }
+    }
+
     pub fn render(&mut self) {
         self.actions.clear();
         self.update_elements();
         self.render_elements();
     }
 
+    // Draws the elements as they are, without delivering any messages.
+    pub fn render_unchanged(&mut self) {
+        self.actions.clear();
+        self.render_elements();
+    }
+
+    // By z-value, elements with equal z-value in insertion order.
+    fn render_order(&self) -> Vec<ElementId> {
+        let mut ids: Vec<ElementId> = self.elements.keys().copied().collect();
+        ids.sort_by_key(|id| (self.elements[id].z_value(), *id));
+        ids
+    }
+
     fn render_elements(&self) {
-        let mut sorted_elements: Vec<&Box<dyn Element>> = self.elements.values().collect();
-        sorted_elements.sort_by(|a, b| a.z_value().cmp(&b.z_value()));
-        sorted_elements.iter().for_each(|e| e.render());
+        self.render_order()
+            .iter()
+            .for_each(|id| self.elements[id].render());
     }
 
     fn update_elements(&mut self) {
@@ -138,3 +177,92 @@ impl Controller {
         self.subscribers.get_mut(&source).unwrap().push(subscriber);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::common::coord::HexCoord;
+    use crate::core::entities::Player;
+    use crate::frontend::animation::FlipAnimation;
+    use crate::frontend::elements::animated_token::AnimatedToken;
+    use crate::frontend::elements::token::{player_color, Token};
+
+    use super::*;
+    use crate::frontend::element::ShapeState;
+
+    #[test]
+    fn test_render_order_is_stable() {
+        let mut controller = Controller::new();
+        let mut ids = vec![];
+        for i in 0..20 {
+            let z = if i == 5 { 0 } else { 1 };
+            let token = Token::new_marker_at_coord(HexCoord::new(0, 0), Player::White, z);
+            ids.push(controller.add_element(Box::new(token)));
+        }
+        let mut expected = ids.clone();
+        expected.remove(5);
+        expected.insert(0, ids[5]);
+        assert_eq!(controller.render_order(), expected);
+
+        // ids keep increasing after clearing
+        controller.clear_all();
+        let token = Token::new_marker_at_coord(HexCoord::new(0, 0), Player::White, 1);
+        assert!(controller.add_element(Box::new(token)) > ids[19]);
+    }
+
+    #[test]
+    fn test_animations_pending() {
+        let mut controller = Controller::new();
+        assert!(!controller.animations_pending());
+
+        let animation = FlipAnimation::new_box(
+            player_color(Player::White),
+            player_color(Player::Black),
+        );
+        let token = AnimatedToken::marker(Player::White, HexCoord::new(0, 0), 1, animation);
+        controller.add_element(Box::new(token));
+        assert!(controller.animations_pending());
+
+        controller.tick_animations(0.);
+        assert!(controller.animations_pending());
+        controller.tick_animations(0.25);
+        assert!(!controller.animations_pending());
+    }
+
+    struct TickCounter(Rc<Cell<u32>>);
+
+    impl Element for TickCounter {
+        fn render(&self) {}
+        fn update(&mut self, _message: &Message) -> Option<UiAction> {
+            None
+        }
+        fn handle_event(&self, _event: &Event) -> Vec<Message> {
+            vec![]
+        }
+        fn set_state(&mut self, _state: ShapeState) {}
+        fn z_value(&self) -> i32 {
+            0
+        }
+        fn tick(&mut self, _time: f64) -> Option<UiAction> {
+            self.0.set(self.0.get() + 1);
+            Some(UiAction::AnimationInProgress)
+        }
+    }
+
+    #[test]
+    fn test_animations_tick_without_events() {
+        let mut controller = Controller::new();
+        let ticks = Rc::new(Cell::new(0));
+        controller.add_element(Box::new(TickCounter(ticks.clone())));
+        // inactive elements animate too, e.g. markers of a run
+        controller.add_element_inactive(Box::new(TickCounter(ticks.clone())));
+
+        for frame in 0..3 {
+            assert!(!controller.has_pending_events());
+            controller.tick_animations(frame as f64 / 60.);
+        }
+        assert_eq!(ticks.get(), 6);
+        assert_eq!(controller.get_actions().len(), 6);
+    }
+}
@@ -28,4 +28,13 @@ pub trait Element {
     fn handle_event(&self, event: &Event) -> Vec<Message>;
     fn set_state(&mut self, state: ShapeState);
     fn z_value(&self) -> i32;
+    // True while the element runs an unfinished animation.
+    fn is_animated(&self) -> bool {
+        false
+    }
+    // Advances animations to the frame time `time` (seconds), once per frame
+    // whether or not there was input.
+    fn tick(&mut self, _time: f64) -> Option<UiAction> {
+        None
+    }
 }
@@ -1,7 +1,7 @@
 use macroquad::prelude::*;
 
 use crate::{
-    common::coord::Point,
+    common::coord::{norm_squared, Point},
     core::game::UiAction,
     frontend::{
         element::{Element, ShapeState},
@@ -33,6 +33,24 @@ impl AllowedMovesIndicator {
     fn set_pos(&mut self, pos: Point) {
         self.pos = pos
     }
+
+    fn draw_arrow_head(&self) {
+        let dir = self.target - self.pos;
+        let len = norm_squared(&dir).sqrt();
+        if len == 0. {
+            return;
+        }
+        let dir = dir / len;
+        let normal = Point(-dir.1, dir.0);
+        let base = self.target - dir * 0.3;
+        let (left, right) = (base + normal * 0.15, base - normal * 0.15);
+        draw_triangle(
+            vec2(self.target.0, self.target.1),
+            vec2(left.0, left.1),
+            vec2(right.0, right.1),
+            BLUE,
+        );
+    }
 }
 
 impl Element for AllowedMovesIndicator {
@@ -46,6 +64,7 @@ impl Element for AllowedMovesIndicator {
                 0.1,
                 BLUE,
             );
+            self.draw_arrow_head();
         }
     }
 
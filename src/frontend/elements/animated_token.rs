@@ -9,7 +9,7 @@ use crate::{
 };
 use macroquad::prelude::*;
 
-use super::token::Token;
+use super::token::{player_color, Token};
 
 pub struct AnimatedToken {
     token: Token,
@@ -61,6 +61,14 @@ impl AnimatedToken {
     pub fn contains(&self, pos: Point) -> bool {
         self.token.contains(pos)
     }
+
+    pub fn color(&self) -> Color {
+        self.token.color()
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
 }
 
 impl Element for AnimatedToken {
@@ -76,16 +84,20 @@ impl Element for AnimatedToken {
 
         match message {
             Message::FlipMarker(_) => {
-                let animation = FlipAnimation::new(WHITE, RED);
+                let player = self.token.player()?;
+                self.animation = Some(FlipAnimation::new_box(
+                    player_color(player),
+                    player_color(player.other()),
+                ));
                 return Some(UiAction::AnimationInProgress);
             }
-            Message::Tick => {
+            Message::Tick(time) => {
                 if self.animation.is_none() {
                     return Some(UiAction::AnimationFinished);
                 }
                 let animation = self.animation.as_mut().unwrap();
 
-                animation.tick();
+                animation.tick(*time);
                 animation.apply(&mut self.token);
                 if self.animation.as_ref().unwrap().finished() {
                     return Some(UiAction::AnimationFinished);
@@ -109,12 +121,19 @@ impl Element for AnimatedToken {
             }
             _ => (),
         }
-        if self.animation.is_some() {
-            res.push(Message::Tick);
-        }
         res
     }
 
+    // Reports AnimationFinished once and then drops the animation.
+    fn tick(&mut self, time: f64) -> Option<UiAction> {
+        self.animation.as_ref()?;
+        let action = self.update(&Message::Tick(time));
+        if action == Some(UiAction::AnimationFinished) {
+            self.animation = None;
+        }
+        action
+    }
+
     fn set_state(&mut self, state: ShapeState) {
         self.token.set_state(state);
     }
@@ -122,4 +141,58 @@ impl Element for AnimatedToken {
     fn z_value(&self) -> i32 {
         self.token.z_value()
     }
+
+    fn is_animated(&self) -> bool {
+        self.animation.as_ref().is_some_and(|a| !a.finished())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flip_marker_installs_flip_animation() {
+        let c = HexCoord::new(1, 1);
+        let mut token = AnimatedToken {
+            token: Token::new_marker_at_coord(c, Player::White, 1),
+            animation: None,
+        };
+
+        let msgs = token.handle_event(&Event::FlipMarker(c));
+        assert_eq!(msgs, vec![Message::FlipMarker(c)]);
+        assert_eq!(token.update(&msgs[0]), Some(UiAction::AnimationInProgress));
+        assert!(token.is_animated());
+
+        assert_eq!(token.update(&Message::Tick(5.)), Some(UiAction::AnimationInProgress));
+        assert_eq!(token.update(&Message::Tick(5.25)), Some(UiAction::AnimationFinished));
+        assert_eq!(token.color(), BLACK);
+        assert!(!token.is_animated());
+    }
+
+    #[test]
+    fn test_tick_advances_without_events() {
+        let animation = FlipAnimation::new_box(WHITE, BLACK);
+        let mut token = AnimatedToken::marker(Player::White, HexCoord::new(0, 0), 1, animation);
+        assert!(token
+            .handle_event(&Event::CursorMoved(HexCoord::new(1, 1)))
+            .is_empty());
+
+        assert_eq!(token.tick(0.), Some(UiAction::AnimationInProgress));
+        assert_eq!(token.tick(0.25), Some(UiAction::AnimationFinished));
+        assert_eq!(token.color(), BLACK);
+        assert_eq!(token.tick(0.3), None);
+    }
+
+    #[test]
+    fn test_flip_marker_ignores_other_coords() {
+        let c = HexCoord::new(1, 1);
+        let token = AnimatedToken {
+            token: Token::new_marker_at_coord(c, Player::Black, 1),
+            animation: None,
+        };
+
+        assert!(token.handle_event(&Event::FlipMarker(HexCoord::new(0, 0))).is_empty());
+        assert!(!token.is_animated());
+    }
 }
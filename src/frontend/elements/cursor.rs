@@ -0,0 +1,137 @@
+use macroquad::prelude::*;
+
+use crate::{
+    common::coord::{Direction, HexCoord, Point},
+    core::{board::Board, game::UiAction},
+    frontend::{
+        element::{Element, ShapeState},
+        events::{Event, Message},
+    },
+};
+
+// Up/Down move vertically, Left/Right along the upper diagonals and with Shift
+// held along the lower diagonals.
+pub fn key_direction(key: KeyCode, shift: bool) -> Option<Direction> {
+    match (key, shift) {
+        (KeyCode::Up, _) => Some(Direction::N),
+        (KeyCode::Down, _) => Some(Direction::S),
+        (KeyCode::Right, false) => Some(Direction::NE),
+        (KeyCode::Right, true) => Some(Direction::SE),
+        (KeyCode::Left, false) => Some(Direction::NW),
+        (KeyCode::Left, true) => Some(Direction::SW),
+        _ => None,
+    }
+}
+
+// Neighbor of `coord` in `dir`; the cursor stays put at the board boundary.
+pub fn cursor_neighbor(board: &Board, coord: HexCoord, dir: Direction) -> HexCoord {
+    let next = coord + dir.dir_vec();
+    if board.is_on_board(&next) {
+        next
+    } else {
+        coord
+    }
+}
+
+// Keyboard focus on a board field, moved by CursorMoved events.
+pub struct CursorElement {
+    coord: HexCoord,
+    z_value: i32,
+}
+
+impl CursorElement {
+    pub fn new(coord: HexCoord, z_value: i32) -> Self {
+        Self { coord, z_value }
+    }
+
+    pub fn coord(&self) -> HexCoord {
+        self.coord
+    }
+}
+
+impl Element for CursorElement {
+    fn render(&self) {
+        let pos = Point::from(self.coord);
+        draw_circle_lines(pos.0, pos.1, 0.3, 0.06, GOLD);
+    }
+
+    fn update(&mut self, message: &Message) -> Option<UiAction> {
+        match message {
+            Message::CursorMoved(coord) => {
+                self.coord = *coord;
+                None
+            }
+            Message::CursorCommit => Some(UiAction::ActionAtCoord(self.coord)),
+            _ => None,
+        }
+    }
+
+    fn handle_event(&self, event: &Event) -> Vec<Message> {
+        match event {
+            Event::CursorMoved(coord) => vec![Message::CursorMoved(*coord)],
+            Event::CursorCommit => vec![Message::CursorCommit],
+            _ => vec![],
+        }
+    }
+
+    fn set_state(&mut self, _state: ShapeState) {}
+
+    fn z_value(&self) -> i32 {
+        self.z_value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEYS: [(KeyCode, bool); 6] = [
+        (KeyCode::Up, false),
+        (KeyCode::Down, false),
+        (KeyCode::Right, false),
+        (KeyCode::Right, true),
+        (KeyCode::Left, false),
+        (KeyCode::Left, true),
+    ];
+
+    #[test]
+    fn test_keys_cover_all_directions() {
+        let dirs: Vec<Direction> = KEYS
+            .iter()
+            .filter_map(|(k, s)| key_direction(*k, *s))
+            .collect();
+        assert!(Direction::all().iter().all(|d| dirs.contains(d)));
+        assert_eq!(key_direction(KeyCode::Enter, false), None);
+    }
+
+    #[test]
+    fn test_cursor_stays_on_board() {
+        let board = Board::new();
+        let coords = board.board_coords();
+        for c in &coords {
+            for (key, shift) in KEYS {
+                let dir = key_direction(key, shift).unwrap();
+                assert!(coords.contains(&cursor_neighbor(&board, *c, dir)));
+            }
+        }
+
+        let top = HexCoord::new(0, 4);
+        assert_eq!(cursor_neighbor(&board, top, Direction::N), top);
+        assert_eq!(
+            cursor_neighbor(&board, HexCoord::new(0, 0), Direction::N),
+            HexCoord::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn test_cursor_commit() {
+        let mut cursor = CursorElement::new(HexCoord::new(0, 0), 5);
+        let c = HexCoord::new(1, 1);
+        for msg in cursor.handle_event(&Event::CursorMoved(c)) {
+            cursor.update(&msg);
+        }
+        assert_eq!(cursor.coord(), c);
+        let msgs = cursor.handle_event(&Event::CursorCommit);
+        assert_eq!(cursor.update(&msgs[0]), Some(UiAction::ActionAtCoord(c)));
+    }
+}
@@ -0,0 +1,112 @@
+use macroquad::prelude::*;
+
+use crate::{
+    common::coord::{distance_squared, HexCoord, Point},
+    core::game::UiAction,
+    frontend::{
+        element::{Element, ShapeState},
+        events::{Event, Message},
+    },
+};
+
+// All clickable fields of a phase in one element, replacing one FieldMarker per
+// field: a click costs a single nearest-field search.
+pub struct FieldGridElement {
+    fields: Vec<(HexCoord, Point)>,
+    radius: f32,
+    mouse_radius: f32,
+    z_value: i32,
+}
+
+impl FieldGridElement {
+    pub fn new(coords: &[HexCoord], radius: f32, mouse_radius: f32, z_value: i32) -> Self {
+        Self {
+            fields: coords.iter().map(|c| (*c, Point::from(*c))).collect(),
+            radius,
+            mouse_radius,
+            z_value,
+        }
+    }
+
+    pub fn field_at(&self, pos: Point) -> Option<HexCoord> {
+        self.fields
+            .iter()
+            .map(|(c, pt)| (c, distance_squared(pt, &pos)))
+            .filter(|(_, d)| *d <= self.mouse_radius.powi(2))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(c, _)| *c)
+    }
+}
+
+impl Element for FieldGridElement {
+    fn render(&self) {
+        for (_, pt) in &self.fields {
+            draw_circle(pt.0, pt.1, self.radius, BLUE);
+        }
+    }
+
+    fn update(&mut self, message: &Message) -> Option<UiAction> {
+        match message {
+            Message::MouseClicked(c) => Some(UiAction::ActionAtCoord(*c)),
+            _ => None,
+        }
+    }
+
+    fn handle_event(&self, event: &Event) -> Vec<Message> {
+        match event {
+            Event::Mouse(mouse_event) if mouse_event.left_clicked => self
+                .field_at(mouse_event.pos)
+                .map(Message::MouseClicked)
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn set_state(&mut self, _state: ShapeState) {}
+
+    fn z_value(&self) -> i32 {
+        self.z_value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::frontend::mouse::MouseEvent;
+
+    use super::*;
+
+    fn click_at(pos: Point) -> Event {
+        Event::Mouse(MouseEvent {
+            pos,
+            last_pos: pos,
+            coord: None,
+            legal_move_coord: None,
+            left_clicked: true,
+            right_clicked: false,
+        })
+    }
+
+    #[test]
+    fn test_click_hits_nearest_field() {
+        let coords = [
+            HexCoord::new(0, 0),
+            HexCoord::new(0, 1),
+            HexCoord::new(1, 1),
+        ];
+        let mut grid = FieldGridElement::new(&coords, 0.1, 0.3, 1);
+
+        let near = Point::from(HexCoord::new(0, 1)) + Point(0.1, -0.1);
+        let msgs = grid.handle_event(&click_at(near));
+        assert_eq!(msgs, vec![Message::MouseClicked(HexCoord::new(0, 1))]);
+        assert_eq!(
+            grid.update(&msgs[0]),
+            Some(UiAction::ActionAtCoord(HexCoord::new(0, 1)))
+        );
+
+        // between fields and off the grid
+        let between = (Point::from(coords[0]) + Point::from(coords[1])) / 2.;
+        assert!(grid.handle_event(&click_at(between)).is_empty());
+        assert!(grid.handle_event(&click_at(Point(3., 3.))).is_empty());
+    }
+}
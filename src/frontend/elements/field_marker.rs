@@ -1,7 +1,8 @@
 use macroquad::prelude::*;
 
 use crate::{
-    common::coord::{distance_squared, HexCoord, Point},
+    common::coord::{HexCoord, Point},
+    common::geometry::point_in_circle,
     core::game::UiAction,
     frontend::{
         element::{Element, ShapeState},
@@ -43,7 +44,7 @@ impl FieldMarker {
     }
 
     fn contains(&self, pos: Point) -> bool {
-        distance_squared(&self.pos, &pos) <= self.mouse_radius.powi(2)
+        point_in_circle(pos, self.pos, self.mouse_radius)
     }
 }
 
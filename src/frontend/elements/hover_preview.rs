@@ -0,0 +1,111 @@
+use macroquad::prelude::*;
+
+use crate::{
+    common::coord::Point,
+    core::game::UiAction,
+    frontend::{
+        element::{Element, ShapeState},
+        events::{Event, Message},
+    },
+};
+
+use super::token::Token;
+
+// Ghost of the piece a click would place or remove, shown only while the mouse
+// is over a legal target.
+pub struct HoverPreview {
+    token: Token,
+}
+
+impl HoverPreview {
+    pub fn new(mut token: Token, color: Color) -> Self {
+        token.set_color(color);
+        token.set_state(ShapeState::Invisible);
+        Self { token }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.token.state() != ShapeState::Invisible
+    }
+}
+
+impl Element for HoverPreview {
+    fn render(&self) {
+        self.token.render();
+    }
+
+    fn update(&mut self, message: &Message) -> Option<UiAction> {
+        match message {
+            Message::ElementMoved(pos) => self.token.set_pos(*pos),
+            Message::ElementShow => self.token.set_state(ShapeState::Visible),
+            Message::ElementHide => self.token.set_state(ShapeState::Invisible),
+            _ => (),
+        }
+        None
+    }
+
+    fn handle_event(&self, event: &Event) -> Vec<Message> {
+        let mut res = vec![];
+        if let Event::Mouse(mouse_event) = event {
+            if let Some(pos) = mouse_event.legal_move_coord.map(Point::from) {
+                if !self.is_visible() {
+                    res.push(Message::ElementShow);
+                }
+                res.push(Message::ElementMoved(pos));
+            } else if self.is_visible() {
+                res.push(Message::ElementHide);
+            }
+        }
+        res
+    }
+
+    fn set_state(&mut self, _state: ShapeState) {}
+
+    fn z_value(&self) -> i32 {
+        self.token.z_value()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coord::HexCoord;
+    use crate::core::entities::Player;
+    use crate::frontend::mouse::MouseEvent;
+
+    use super::*;
+
+    fn hover(legal_move_coord: Option<HexCoord>) -> Event {
+        Event::Mouse(MouseEvent {
+            pos: Point(0.1, 0.1),
+            last_pos: Point(0.1, 0.1),
+            coord: None,
+            legal_move_coord,
+            left_clicked: false,
+            right_clicked: false,
+        })
+    }
+
+    #[test]
+    fn test_preview_follows_legal_targets() {
+        let token = Token::new_marker_at_point(Point(0., 0.), Player::White, 10);
+        let mut preview = HoverPreview::new(token, RED);
+        assert!(!preview.is_visible());
+
+        let c = HexCoord::new(1, 1);
+        for msg in preview.handle_event(&hover(Some(c))) {
+            preview.update(&msg);
+        }
+        assert!(preview.is_visible());
+        assert_eq!(preview.token().pos(), Point::from(c));
+        assert_eq!(preview.token().color(), RED);
+
+        for msg in preview.handle_event(&hover(None)) {
+            preview.update(&msg);
+        }
+        assert!(!preview.is_visible());
+    }
+}
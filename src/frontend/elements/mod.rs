@@ -2,4 +2,7 @@ pub mod token;
 pub mod field_marker;
 pub mod run_indicator;
 pub mod allowed_moves_indicator;
-pub mod animated_token;
\ No newline at end of file
+pub mod animated_token;
+pub mod cursor;
+pub mod field_grid;
+pub mod hover_preview;
@@ -2,6 +2,7 @@ use macroquad::prelude::*;
 
 use crate::{
     common::coord::{HexCoord, Point},
+    common::geometry::point_in_oriented_rect,
     core::game::UiAction,
     frontend::{
         element::{Element, ShapeState},
@@ -14,11 +15,12 @@ pub struct RunIndicator {
     corners: [Vec2; 4],
     dir: Vec2,
     perp: Vec2,
-    coord: Option<HexCoord>,
+    run: Option<Vec<HexCoord>>,
     width: f32,
     height: f32,
     z_value: i32,
     color: Color,
+    base_color: Color,
     value: Option<HexCoord>,
     mouse_entered: bool,
 }
@@ -37,9 +39,10 @@ impl RunIndicator {
             dir,
             perp,
             color: BLACK,
+            base_color: BLACK,
             width,
             height,
-            coord: None,
+            run: None,
             value: None,
             mouse_entered: false,
         }
@@ -54,8 +57,19 @@ impl RunIndicator {
         Self::from_segment_points(coord0.into(), coord1.into(), height, z_value)
     }
 
-    pub fn set_coord(&mut self, coord: HexCoord) {
-        self.coord = Some(coord);
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self.base_color = color;
+        self
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    // Makes the indicator selectable; clicking it removes exactly this run.
+    pub fn set_run(&mut self, run: Vec<HexCoord>) {
+        self.run = Some(run);
     }
 
     pub fn from_segment_points(pt0: Point, pt1: Point, height: f32, z_value: i32) -> Self {
@@ -78,9 +92,10 @@ impl RunIndicator {
             dir,
             perp,
             color: BLACK,
+            base_color: BLACK,
             width,
             height,
-            coord: None,
+            run: None,
             value: None,
             mouse_entered: false,
         }
@@ -99,16 +114,15 @@ impl RunIndicator {
     fn contains(&self, pos: Point) -> bool {
         let height = (self.corners[0] - self.corners[1]).length();
         let start = self.corners[0] - self.perp * height / 2.;
-        let pt = vec2(pos.0, pos.1);
-
-        let diff = pt - start;
-
-        let proj = diff.dot(self.dir);
-        if proj < 0. || proj > (self.corners[1] - self.corners[2]).length() {
-            return false;
-        }
-
-        (diff - proj * self.dir).length_squared() <= (height / 2.).powi(2)
+        let length = (self.corners[1] - self.corners[2]).length();
+
+        point_in_oriented_rect(
+            pos,
+            Point(start.x, start.y),
+            Point(self.dir.x, self.dir.y),
+            length,
+            height / 2.,
+        )
     }
 }
 
@@ -130,15 +144,18 @@ impl Element for RunIndicator {
     fn update(&mut self, message: &Message) -> Option<UiAction> {
         match message {
             Message::MouseEntered => {
-                self.color = GREEN;
+                // only selectable runs are highlighted
+                if self.run.is_some() {
+                    self.color = GREEN;
+                }
                 self.mouse_entered = true;
                 None
             }
             Message::MouseLeft => {
-                self.color = BLACK;
+                self.color = self.base_color;
                 None
             }
-            Message::MouseClicked(_) => self.coord.map(|c| UiAction::ActionAtCoord(c)),
+            Message::MouseClicked(_) => self.run.clone().map(UiAction::RemoveRunAt),
             _ => None,
         }
     }
@@ -154,11 +171,10 @@ impl Element for RunIndicator {
                 if self.contains(mouse_event.pos) {
                     res.push(Message::MouseInside);
                 }
-                if mouse_event.left_clicked
-                    && self.contains(mouse_event.pos)
-                    && self.coord.is_some()
-                {
-                    res.push(Message::MouseClicked(self.coord.unwrap()));
+                if let Some(run) = &self.run {
+                    if mouse_event.left_clicked && self.contains(mouse_event.pos) {
+                        res.push(Message::MouseClicked(run[0]));
+                    }
                 }
             }
             _ => (),
@@ -3,7 +3,8 @@ use std::path::Ancestors;
 use macroquad::prelude::*;
 
 use crate::{
-    common::coord::{distance_squared, HexCoord, Point},
+    common::coord::{HexCoord, Point},
+    common::geometry::point_in_circle,
     core::{entities::Player, game::UiAction},
     frontend::{
         animation::*,
@@ -16,13 +17,16 @@ use crate::{
 
 use super::animated_token::AnimatedToken;
 
-fn player_color(player: Player) -> Color {
+pub fn player_color(player: Player) -> Color {
     match player {
         Player::White => WHITE,
         Player::Black => BLACK,
     }
 }
 
+// Textures are optional; without them tokens are drawn as meshes. Loading them
+// needs a graphics context, so headless code sticks to TokenConfig::new().
+#[derive(Clone)]
 pub struct TokenConfig {
     pub ring_inner_radius: f32,
     pub ring_outer_radius: f32,
@@ -31,6 +35,9 @@ pub struct TokenConfig {
     pub black_player_color: Color,
     pub default_hover_color: Color,
     pub remove_hover_color: Color,
+    pub remove_target_outline_color: Color,
+    pub ring_texture: Option<Texture2D>,
+    pub marker_texture: Option<Texture2D>,
 }
 
 impl TokenConfig {
@@ -43,8 +50,18 @@ impl TokenConfig {
             black_player_color: BLACK,
             default_hover_color: BLUE,
             remove_hover_color: RED,
+            remove_target_outline_color: ORANGE,
+            ring_texture: None,
+            marker_texture: None,
         }
     }
+
+    // The textures are tinted with the player color, so light images work best.
+    pub async fn with_textures(mut self, ring: &str, marker: &str) -> Result<Self, FileError> {
+        self.ring_texture = Some(load_texture(ring).await?);
+        self.marker_texture = Some(load_texture(marker).await?);
+        Ok(self)
+    }
 }
 
 #[derive(Clone)]
@@ -59,8 +76,10 @@ pub struct TokenBuilder {
     pub token_type: Option<TokenType>,
     default_color: Option<Color>,
     hover_color: Option<Color>,
+    outline_color: Option<Color>,
     state: Option<ShapeState>,
     z_value: Option<i32>,
+    player: Option<Player>,
     config: TokenConfig,
 }
 
@@ -73,8 +92,10 @@ impl TokenBuilder {
             token_type: None,
             default_color: None,
             hover_color: Some(config.default_hover_color),
+            outline_color: None,
             state: Some(ShapeState::Visible),
             z_value: Some(0),
+            player: None,
             config,
         }
     }
@@ -95,6 +116,7 @@ impl TokenBuilder {
     }
 
     fn set_player(&mut self, player: Player) {
+        self.player = Some(player);
         match player {
             Player::Black => {
                 self.default_color = Some(self.config.black_player_color);
@@ -105,11 +127,24 @@ impl TokenBuilder {
         }
     }
 
+    pub fn config(&mut self, config: TokenConfig) -> &mut Self {
+        self.hover_color = Some(config.default_hover_color);
+        self.config = config;
+        self
+    }
+
     pub fn remove_hover_color(&mut self) -> &mut Self {
         self.hover_color = Some(self.config.remove_hover_color);
         self
     }
 
+    // Styling for rings that can be removed after a run: outlined and hoverable.
+    pub fn remove_target(&mut self) -> &mut Self {
+        self.remove_hover_color();
+        self.outline_color = Some(self.config.remove_target_outline_color);
+        self.state(ShapeState::Hoverable)
+    }
+
     pub fn state(&mut self, state: ShapeState) -> &mut Self {
         self.state = Some(state);
         self
@@ -136,6 +171,10 @@ impl TokenBuilder {
     }
 
     pub fn build(&mut self) -> Token {
+        let texture = match self.token_type {
+            Some(TokenType::Ring(..)) => self.config.ring_texture,
+            _ => self.config.marker_texture,
+        };
         Token {
             pos: self.pos,
             coord: self.coord,
@@ -143,9 +182,12 @@ impl TokenBuilder {
             color: self.default_color.unwrap(),
             default_color: self.default_color.unwrap(),
             hover_color: self.hover_color.unwrap(),
+            outline_color: self.outline_color,
             state: self.state.unwrap(),
             z_value: self.z_value.unwrap(),
+            player: self.player,
             mouse_entered: false,
+            texture,
         }
     }
 }
@@ -158,9 +200,12 @@ pub struct Token {
     color: Color,
     default_color: Color,
     hover_color: Color,
+    outline_color: Option<Color>,
     state: ShapeState,
     z_value: i32,
+    player: Option<Player>,
     mouse_entered: bool,
+    texture: Option<Texture2D>,
 }
 
 impl Token {
@@ -178,9 +223,12 @@ impl Token {
             color,
             default_color: color,
             hover_color: BLUE,
+            outline_color: None,
             state: ShapeState::Visible,
             z_value,
+            player: None,
             mouse_entered: false,
+            texture: None,
         }
     }
 
@@ -192,13 +240,15 @@ impl Token {
     }
 
     pub fn new_marker_at_point(pos: Point, player: Player, z_value: i32) -> Self {
-        Token::new(
+        let mut elem = Token::new(
             pos,
             None,
             TokenType::Marker(0.2),
             player_color(player),
             z_value,
-        )
+        );
+        elem.player = Some(player);
+        elem
     }
 
     pub fn new_ring_at_coord(coord: HexCoord, player: Player, z_value: i32) -> Self {
@@ -209,20 +259,28 @@ impl Token {
     }
 
     pub fn new_ring_at_point(pos: Point, player: Player, z_value: i32) -> Self {
-        Token::new(
+        let mut elem = Token::new(
             pos,
             None,
             TokenType::Ring(0.4, 0.2),
             player_color(player),
             z_value,
-        )
+        );
+        elem.player = Some(player);
+        elem
     }
 
     pub fn draw(&self, color: Color) {
+        if let Some(texture) = self.texture {
+            self.draw_texture(texture, color);
+            return;
+        }
         match self.shape_type {
             TokenType::Ring(radius_outer, radius_inner) => {
-                draw_circle_lines(self.pos.0, self.pos.1, radius_outer, 0.03, BLACK);
-                draw_circle_lines(self.pos.0, self.pos.1, radius_inner, 0.03, BLACK);
+                let (outline, thickness) =
+                    self.outline_color.map_or((BLACK, 0.03), |c| (c, 0.08));
+                draw_circle_lines(self.pos.0, self.pos.1, radius_outer, thickness, outline);
+                draw_circle_lines(self.pos.0, self.pos.1, radius_inner, thickness, outline);
                 draw_ring_mesh(self.pos.0, self.pos.1, radius_inner, radius_outer, color);
             }
             TokenType::Marker(radius) => {
@@ -232,14 +290,53 @@ impl Token {
         }
     }
 
+    fn draw_texture(&self, texture: Texture2D, color: Color) {
+        let radius = match self.shape_type {
+            TokenType::Ring(outer, _) => outer,
+            TokenType::Marker(radius) => radius,
+        };
+        let params = DrawTextureParams {
+            dest_size: Some(vec2(2. * radius, 2. * radius)),
+            ..Default::default()
+        };
+        draw_texture_ex(texture, self.pos.0 - radius, self.pos.1 - radius, color, params);
+        if let Some(outline) = self.outline_color {
+            draw_circle_lines(self.pos.0, self.pos.1, radius, 0.08, outline);
+        }
+    }
+
+    pub fn has_texture(&self) -> bool {
+        self.texture.is_some()
+    }
+
     pub fn set_color(&mut self, color: Color) {
         self.color = color;
     }
 
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn player(&self) -> Option<Player> {
+        self.player
+    }
+
+    pub fn outline_color(&self) -> Option<Color> {
+        self.outline_color
+    }
+
+    pub fn hover_color(&self) -> Color {
+        self.hover_color
+    }
+
+    pub fn state(&self) -> ShapeState {
+        self.state
+    }
+
     pub fn contains(&self, pos: Point) -> bool {
         match self.shape_type {
-            TokenType::Marker(radius) => distance_squared(&self.pos, &pos) <= radius.powi(2),
-            TokenType::Ring(outer, _) => distance_squared(&self.pos, &pos) <= outer.powi(2),
+            TokenType::Marker(radius) => point_in_circle(pos, self.pos, radius),
+            TokenType::Ring(outer, _) => point_in_circle(pos, self.pos, outer),
         }
     }
 
@@ -321,3 +418,27 @@ impl Element for Token {
         self.z_value
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builds_without_textures() {
+        let config = TokenConfig::new();
+        assert!(config.ring_texture.is_none() && config.marker_texture.is_none());
+
+        let ring = TokenBuilder::new()
+            .config(config.clone())
+            .ring(Player::Black)
+            .coord(HexCoord::new(1, 0))
+            .build();
+        assert!(!ring.has_texture());
+        assert_eq!(ring.color(), config.black_player_color);
+        assert!(matches!(ring.shape_type, TokenType::Ring(..)));
+        assert!(ring.contains(Point::from(HexCoord::new(1, 0))));
+
+        let marker = TokenBuilder::new().marker(Player::White).build();
+        assert!(!marker.has_texture());
+    }
+}
@@ -11,8 +11,11 @@ pub enum Message {
     ElementShow,
     ElementHide,
     MouseClicked(HexCoord),
-    Tick,
+    // frame time in seconds
+    Tick(f64),
     FlipMarker(HexCoord),
+    CursorMoved(HexCoord),
+    CursorCommit,
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -23,4 +26,6 @@ pub enum Event {
     RemoveRing(HexCoord),
     MoveRing(HexCoord, HexCoord),
     PlaceRing(Player, HexCoord),
+    CursorMoved(HexCoord),
+    CursorCommit,
 }
@@ -7,9 +7,13 @@ use crate::core::command::*;
 use crate::core::entities::*;
 use crate::core::game::*;
 use crate::core::state::*;
+use crate::frontend::animation::now;
+use crate::frontend::animation::AnimationConfig;
 use crate::frontend::animation::FlipAnimation;
 use crate::frontend::animation::MoveAnimation;
+use crate::frontend::animation::PlaceAnimation;
 use crate::frontend::animation::RemoveAnimation;
+use crate::frontend::animation::TurnChangeAnimation;
 
 use super::controller::Controller;
 use super::controller::ElementId;
@@ -17,12 +21,17 @@ use super::element::Element;
 use super::element::ShapeState;
 use super::elements::allowed_moves_indicator::*;
 use super::elements::animated_token::AnimatedToken;
+use super::elements::cursor::*;
+use super::elements::field_grid::FieldGridElement;
 use super::elements::field_marker::*;
+use super::elements::hover_preview::HoverPreview;
 use super::elements::run_indicator::*;
 use super::elements::token::*;
 use super::events::Event;
-use super::mouse::MouseHandler;
+use super::mouse::{InputMode, MouseEvent, MouseHandler};
 use super::primitives::build_grid_lines;
+use super::scoreboard::draw_scoreboard;
+use super::strings::Strings;
 use macroquad::prelude::*;
 
 pub type ShapeId = usize;
@@ -34,6 +43,175 @@ mod consts {
     const WHITE_PLAYER_COLOR: Color = WHITE;
 }
 
+// Seconds between the flips of two neighboring markers on a ring's path.
+const FLIP_STEP_DELAY: f64 = 0.05;
+
+// Markers flip one after another, starting next to where the ring left.
+fn flip_delay(from: HexCoord, c: HexCoord) -> f64 {
+    let (x0, y0, z0) = from.to_cube();
+    let (x1, y1, z1) = c.to_cube();
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).max((z1 - z0).abs());
+    (steps - 1).max(0) as f64 * FLIP_STEP_DELAY
+}
+
+// Elements only need events and updates when the mouse moved or clicked, or
+// while an animation runs.
+fn frame_dirty(mouse_event: &MouseEvent, animations_pending: bool) -> bool {
+    mouse_event.pos != mouse_event.last_pos
+        || mouse_event.left_clicked
+        || mouse_event.right_clicked
+        || animations_pending
+}
+
+// Makes clear whose turn it is to remove, which is not always the last mover,
+// and who won once the game is over.
+fn phase_banner(state: &State, strings: &Strings) -> Option<String> {
+    match state.current_phase {
+        Phase::PlayerWon(player) => return Some(strings.fill(&strings.wins, player)),
+        Phase::Draw => return Some(strings.draw.clone()),
+        _ => (),
+    }
+    let player = state.resolving_player()?;
+    let template = if state.current_phase == Phase::RemoveRun {
+        &strings.removes_run
+    } else {
+        &strings.removes_ring
+    };
+    Some(strings.fill(template, player))
+}
+
+// What clicking `coord` does, for the tooltip next to the cursor.
+fn move_tooltip(
+    legal_moves: &[Action],
+    board: &Board,
+    coord: HexCoord,
+    strings: &Strings,
+) -> Option<String> {
+    let action = legal_moves.iter().find(|a| a.coord() == coord)?;
+    let text = match action {
+        Action::PlaceRing(_) => strings.tooltip_place_ring.clone(),
+        Action::PlaceMarker(_) => strings.tooltip_place_marker.clone(),
+        Action::MoveRing(m) => match board.flip_count(&m.from, &m.to) {
+            0 => strings.tooltip_move_ring.clone(),
+            1 => strings.tooltip_flips_one.clone(),
+            n => Strings::fill_numbers(&strings.tooltip_flips, &[("count", n)]),
+        },
+        Action::RemoveRun(_) => strings.tooltip_remove_run.clone(),
+        Action::RemoveRing(_) => strings.tooltip_remove_ring.clone(),
+    };
+    Some(text)
+}
+
+// Undo has its own key so that right-click is free for context actions.
+const UNDO_KEY: KeyCode = KeyCode::U;
+
+// Keys that map directly to UiActions.
+const KEY_BINDINGS: &[(KeyCode, UiAction)] = &[
+    (UNDO_KEY, UiAction::Undo),
+    (KeyCode::Escape, UiAction::CancelMove),
+];
+
+// Keys pressed and buttons clicked in one frame.
+#[derive(Clone, Default, Debug)]
+struct FrameInput {
+    keys: Vec<KeyCode>,
+    right_clicked: bool,
+}
+
+fn input_actions(input: &FrameInput) -> Vec<UiAction> {
+    KEY_BINDINGS
+        .iter()
+        .filter(|(key, _)| input.keys.contains(key))
+        .map(|(_, action)| action.clone())
+        .collect()
+}
+
+// The one action a frame yields when several were registered. Undo and cancel
+// are deliberate key presses and win over clicks; between equals the first
+// registered wins. Anything else is not passed on to the game.
+fn frame_action(actions: &[UiAction]) -> UiAction {
+    let priority = |a: &UiAction| match a {
+        UiAction::Undo => Some(0),
+        UiAction::CancelMove => Some(1),
+        UiAction::RemoveRunAt(_) => Some(2),
+        UiAction::ActionAtCoord(_) => Some(3),
+        _ => None,
+    };
+    actions
+        .iter()
+        .filter_map(|a| priority(a).map(|p| (p, a)))
+        .min_by_key(|(p, _)| *p)
+        .map_or(UiAction::NoAction, |(_, a)| a.clone())
+}
+
+// Runs the player can remove, in the order of the RemoveRun legal moves.
+fn removable_runs(legal_moves: &[Action]) -> Vec<Vec<HexCoord>> {
+    legal_moves
+        .iter()
+        .filter_map(|a| match a {
+            Action::RemoveRun(r) => Some(r.run.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Tab selects the next run, Shift-Tab the previous one, wrapping around. The
+// first press starts at the first or the last run respectively.
+fn cycle_run(selected: Option<usize>, n_runs: usize, backwards: bool) -> Option<usize> {
+    if n_runs == 0 {
+        return None;
+    }
+    Some(match (selected, backwards) {
+        (None, false) => 0,
+        (None, true) => n_runs - 1,
+        (Some(i), false) => (i + 1) % n_runs,
+        (Some(i), true) => (i + n_runs - 1) % n_runs,
+    })
+}
+
+fn selected_run_action(runs: &[Vec<HexCoord>], selected: Option<usize>) -> Option<UiAction> {
+    runs.get(selected?).map(|run| UiAction::RemoveRunAt(run.clone()))
+}
+
+// Right-click note on a removable ring: which point removing it scores.
+fn ring_removal_note(points_before: usize, strings: &Strings) -> String {
+    let point = points_before + 1;
+    if point >= POINTS_TO_WIN {
+        strings.note_wins.clone()
+    } else {
+        let values = [("point", point), ("total", POINTS_TO_WIN)];
+        Strings::fill_numbers(&strings.note_scores, &values)
+    }
+}
+
+// Runs of either player that moving the ring to `to` would form, found by
+// playing the move on a copy. Empty outside MoveRing or for other fields.
+fn prospective_runs(state: &State, to: HexCoord) -> Vec<Vec<HexCoord>> {
+    if !matches!(state.current_phase, Phase::MoveRing(_)) {
+        return vec![];
+    }
+    let Some(action) = state.legal_moves().into_iter().find(|a| a.coord() == to) else {
+        return vec![];
+    };
+    let mut after = state.clone();
+    after.make(&action);
+    Player::all()
+        .iter()
+        .flat_map(|p| after.player_runs(*p).clone())
+        .collect()
+}
+
+// World size shown in a window of the given pixel size: the base area fits and
+// the longer side of the window is extended, so the board keeps its aspect ratio.
+pub fn world_size(base: (f32, f32), pixel_width: u32, pixel_height: u32) -> (f32, f32) {
+    let aspect = pixel_width.max(1) as f32 / pixel_height.max(1) as f32;
+    if aspect > base.0 / base.1 {
+        (base.1 * aspect, base.1)
+    } else {
+        (base.0, base.0 / aspect)
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum UiStatus {
     Idle,
@@ -43,6 +221,8 @@ pub enum UiStatus {
 
 pub struct Frontend {
     grid_lines: Vec<[HexCoordF; 2]>,
+    // board plus margins in world units
+    base_size: (f32, f32),
     width: f32,
     height: f32,
     pixel_width: u32,
@@ -58,6 +238,33 @@ pub struct Frontend {
     update_request: bool,
     white_ring_slots: [Point; 3],
     black_ring_slots: [Point; 3],
+    move_summary: Option<String>,
+    banner: Option<String>,
+    scores: [(Player, usize); 2],
+    to_move: Player,
+    // runs while the turn passes to `to_move`
+    turn_change: Option<TurnChangeAnimation>,
+    markers_remaining: usize,
+    tooltip: Option<String>,
+    // where the hovered ring could go, before its marker is placed
+    hover_targets: Vec<HexCoord>,
+    // hovered ring target and the runs moving there would form
+    run_preview: Option<(HexCoord, Vec<Vec<HexCoord>>)>,
+    // shown instead of the tooltip while the cursor stays on this field
+    context_note: Option<(HexCoord, String)>,
+    // index into removable_runs(), chosen with Tab
+    selected_run: Option<usize>,
+    // ring clicked once in RemoveRing, removed by a second click when confirming
+    selected_ring: Option<HexCoord>,
+    confirm_ring_removal: bool,
+    cursor: HexCoord,
+    debug_overlay: bool,
+    // side played at this view, None if both are (hot seat)
+    human_player: Option<Player>,
+    animate_own_moves: bool,
+    animation_config: AnimationConfig,
+    token_config: TokenConfig,
+    strings: Strings,
 }
 
 impl Frontend {
@@ -69,20 +276,21 @@ impl Frontend {
         h_margin: f32,
     ) -> Self {
         let radius = board.get_radius();
-        let width = (2. * radius + w_margin);
-        let height = (2. * radius + h_margin);
+        let base_size = (2. * radius + w_margin, 2. * radius + h_margin);
+        let (width, height) = world_size(base_size, pixel_width, pixel_height);
 
         Frontend {
             grid_lines: build_grid_lines(radius),
-            width: width,
-            height: height,
+            base_size,
+            width,
+            height,
             pixel_width,
             pixel_height,
             legal_moves: vec![],
             phase: Phase::PlaceRing,
             current_player: Player::White,
             controller: Controller::new(),
-            mouse_handler: MouseHandler::new(width, height, pixel_width, pixel_height),
+            mouse_handler: MouseHandler::new(board, width, height, pixel_width, pixel_height),
             ui_actions: vec![],
             run_bboxes: vec![],
             ui_status: UiStatus::Idle,
@@ -97,15 +305,129 @@ impl Frontend {
                 Point(radius - 1., radius),
                 Point(radius - 2., radius),
             ],
+            move_summary: None,
+            banner: None,
+            scores: Player::all().map(|p| (p, 0)),
+            to_move: Player::White,
+            turn_change: None,
+            markers_remaining: MARKER_SUPPLY,
+            tooltip: None,
+            hover_targets: vec![],
+            run_preview: None,
+            context_note: None,
+            selected_run: None,
+            selected_ring: None,
+            confirm_ring_removal: false,
+            cursor: HexCoord::new(0, 0),
+            debug_overlay: false,
+            human_player: Some(Player::White),
+            animate_own_moves: false,
+            animation_config: AnimationConfig::default(),
+            token_config: TokenConfig::new(),
+            strings: Strings::default(),
         }
     }
 
-    fn set_camera(&self) {
-        set_camera(&Camera2D {
+    pub fn with_strings(mut self, strings: Strings) -> Self {
+        self.strings = strings;
+        self
+    }
+
+    // Sizes, colors and optional textures of rings and markers.
+    pub fn with_token_config(mut self, config: TokenConfig) -> Self {
+        self.token_config = config;
+        self
+    }
+
+    fn token_builder(&self) -> TokenBuilder {
+        let mut builder = TokenBuilder::new();
+        builder.config(self.token_config.clone());
+        builder
+    }
+
+    pub fn with_animation_config(mut self, config: AnimationConfig) -> Self {
+        self.animation_config = config;
+        self
+    }
+
+    // Also slide the human's rings to their target instead of snapping.
+    pub fn with_own_move_animation(mut self, flag: bool) -> Self {
+        self.animate_own_moves = flag;
+        self
+    }
+
+    // Removing a ring takes a second click on the same ring.
+    pub fn with_ring_removal_confirm(mut self, flag: bool) -> Self {
+        self.confirm_ring_removal = flag;
+        self
+    }
+
+    // Touch input picks fields from further away than the mouse.
+    pub fn with_input_mode(mut self, mode: InputMode) -> Self {
+        self.mouse_handler.set_input_mode(mode);
+        self
+    }
+
+    fn pick_radius(&self) -> f32 {
+        self.mouse_handler.input_mode().pick_radius()
+    }
+
+    fn is_human(&self, player: Player) -> bool {
+        self.human_player.is_none_or(|h| h == player)
+    }
+
+    // Pieces the user can act on: those of the player to move, if that is a human.
+    fn controls(&self, player: Player) -> bool {
+        player == self.current_player && self.is_human(player)
+    }
+
+    // FPS and element count in the top right corner, toggled with F3.
+    pub fn with_debug_overlay(mut self, flag: bool) -> Self {
+        self.debug_overlay = flag;
+        self
+    }
+
+    // Follows window resizes so that drawing and hit testing use the same mapping.
+    fn fit_to_screen(&mut self, pixel_width: u32, pixel_height: u32) {
+        if (pixel_width, pixel_height) == (self.pixel_width, self.pixel_height) {
+            return;
+        }
+        let (width, height) = world_size(self.base_size, pixel_width, pixel_height);
+        self.width = width;
+        self.height = height;
+        self.pixel_width = pixel_width;
+        self.pixel_height = pixel_height;
+        self.mouse_handler
+            .resize(width, height, pixel_width, pixel_height);
+    }
+
+    fn camera(&self) -> Camera2D {
+        Camera2D {
             zoom: vec2(1. / self.width * 2., 1. / self.height * 2.),
             target: vec2(0., 0.),
             ..Default::default()
+        }
+    }
+
+    fn set_camera(&self) {
+        set_camera(&self.camera());
+    }
+
+    // Draws the board and pieces of `state` into an offscreen target of the
+    // window's size, e.g. for screenshots. Needs the macroquad window, so tests
+    // check the elements via build_elements instead.
+    pub fn render_to_image(&mut self, state: &State) -> Image {
+        self.update_from_state(state);
+        let target = render_target(self.pixel_width, self.pixel_height);
+        set_camera(&Camera2D {
+            render_target: Some(target),
+            ..self.camera()
         });
+        clear_background(LIGHTGRAY);
+        self.draw_grid();
+        self.controller.render_unchanged();
+        set_default_camera();
+        target.texture.get_texture_data()
     }
 
     fn draw_grid(&self) {
@@ -114,38 +436,208 @@ impl Frontend {
         }
     }
 
-    fn update_user_actions(&mut self) {
-        let mouse_event = self.mouse_handler.has_message(None);
+    fn draw_move_summary(&self) {
+        if let Some(summary) = &self.move_summary {
+            set_default_camera();
+            draw_text(summary, 20., 30., 28., DARKGRAY);
+            self.set_camera();
+        }
+        if let Some(banner) = &self.banner {
+            set_default_camera();
+            draw_text(banner, 20., 60., 28., MAROON);
+            self.set_camera();
+        }
+        set_default_camera();
+        let sweep = self.turn_change.map(|a| a.progress());
+        draw_scoreboard(
+            &self.strings,
+            &self.scores,
+            self.to_move,
+            self.markers_remaining,
+            sweep,
+        );
+        self.set_camera();
+    }
 
-        if mouse_event.right_clicked {
-            println!("Right mouse clicked");
-            self.ui_actions.push(UiAction::Undo);
+    fn draw_selected_ring(&self) {
+        if let Some(c) = self.selected_ring {
+            let pt = Point::from(c);
+            draw_circle_lines(pt.0, pt.1, 0.45, 0.06, RED);
         }
     }
 
+    // With confirmation on, the first click on a ring only selects it and the
+    // click is dropped; a click on the selected ring goes through.
+    fn confirm_ring_clicks(&mut self) {
+        if !self.confirm_ring_removal || self.phase != Phase::RemoveRing {
+            return;
+        }
+        let selected = &mut self.selected_ring;
+        self.ui_actions.retain(|a| match a {
+            UiAction::ActionAtCoord(c) if *selected != Some(*c) => {
+                *selected = Some(*c);
+                false
+            }
+            _ => true,
+        });
+    }
+
+    fn draw_hover_targets(&self) {
+        let mut color = player_color(self.current_player);
+        color.a = 0.6;
+        for c in &self.hover_targets {
+            let pt = Point::from(*c);
+            draw_circle(pt.0, pt.1, 0.07, color);
+        }
+    }
+
+    fn draw_run_preview(&self) {
+        let color = Color::new(0.1, 0.6, 0.2, 0.5);
+        for run in self.run_preview.iter().flat_map(|(_, runs)| runs) {
+            let (p0, p1) = (Point::from(run[0]), Point::from(*run.last().unwrap()));
+            draw_line(p0.0, p0.1, p1.0, p1.1, 0.35, color);
+        }
+    }
+
+    fn draw_selected_run(&self) {
+        let runs = removable_runs(&self.legal_moves);
+        if let Some(run) = self.selected_run.and_then(|i| runs.get(i)) {
+            let (p0, p1) = (Point::from(run[0]), Point::from(*run.last().unwrap()));
+            draw_line(p0.0, p0.1, p1.0, p1.1, 0.35, Color::new(1., 0.6, 0., 0.6));
+        }
+    }
+
+    fn draw_tooltip(&self) {
+        if let Some(text) = &self.tooltip {
+            let (x, y) = mouse_position();
+            set_default_camera();
+            draw_text(text, x + 16., y - 8., 20., BLACK);
+            self.set_camera();
+        }
+    }
+
+    fn draw_debug_overlay(&self) {
+        if !self.debug_overlay {
+            return;
+        }
+        let text = format!(
+            "{} fps, {} elements",
+            get_fps(),
+            self.controller.element_count()
+        );
+        set_default_camera();
+        draw_text(&text, screen_width() - 220., 30., 24., DARKGRAY);
+        self.set_camera();
+    }
+
+    fn update_cursor(&mut self, board: &Board) {
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        for key in [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right] {
+            if !is_key_pressed(key) {
+                continue;
+            }
+            if let Some(dir) = key_direction(key, shift) {
+                self.cursor = cursor_neighbor(board, self.cursor, dir);
+                self.controller.schedule_event(Event::CursorMoved(self.cursor));
+            }
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.controller.schedule_event(Event::CursorCommit);
+        }
+    }
+
+    fn update_user_actions(&mut self, mouse_event: &MouseEvent) {
+        let input = FrameInput {
+            keys: KEY_BINDINGS
+                .iter()
+                .map(|(key, _)| *key)
+                .filter(|key| is_key_pressed(*key))
+                .collect(),
+            right_clicked: mouse_event.right_clicked,
+        };
+        if input.right_clicked {
+            self.context_note = self.context_note_at(mouse_event.legal_move_coord);
+        }
+        self.ui_actions.extend(input_actions(&input));
+
+        let runs = removable_runs(&self.legal_moves);
+        if is_key_pressed(KeyCode::Tab) {
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            self.selected_run = cycle_run(self.selected_run, runs.len(), shift);
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.ui_actions
+                .extend(selected_run_action(&runs, self.selected_run));
+        }
+
+        if is_key_pressed(KeyCode::F3) {
+            self.debug_overlay = !self.debug_overlay;
+        }
+    }
+
+    fn context_note_at(&self, coord: Option<HexCoord>) -> Option<(HexCoord, String)> {
+        let coord = coord?;
+        if self.phase != Phase::RemoveRing {
+            return None;
+        }
+        let points = self
+            .scores
+            .iter()
+            .find(|(p, _)| *p == self.to_move)
+            .map_or(0, |(_, n)| *n);
+        Some((coord, ring_removal_note(points, &self.strings)))
+    }
+
     fn add_legal_move_highlights(&mut self, state: &State) {
+        if let Phase::MoveRing(from) = self.phase {
+            for dot in self.ring_destination_dots(from, &state.board) {
+                self.controller.add_element(Box::new(dot));
+            }
+            return;
+        }
+        if self.phase == Phase::PlaceRing {
+            let coords: Vec<HexCoord> = self.legal_moves.iter().map(|a| a.coord()).collect();
+            self.controller
+                .add_element(Box::new(FieldGridElement::new(&coords, 0.1, self.pick_radius(), 1)));
+            return;
+        }
+        let pick_radius = self.pick_radius();
         self.legal_moves.iter().for_each(|action| {
             let coord = action.coord();
             self.controller
-                .add_element(Box::new(FieldMarker::new(coord, 0.1, 0.3, 1)));
+                .add_element(Box::new(FieldMarker::new(coord, 0.1, pick_radius, 1)));
         });
     }
 
-    fn add_ring_element(&mut self, c: HexCoord, player: Player) {
-        let mut builder = TokenBuilder::new();
+    // One small dot per field the ring at `from` can land on.
+    fn ring_destination_dots(&self, from: HexCoord, board: &Board) -> Vec<FieldMarker> {
+        board
+            .ring_targets(&from)
+            .into_iter()
+            .map(|c| FieldMarker::new(c, 0.07, self.pick_radius(), 1))
+            .collect()
+    }
+
+    fn ring_token(&self, c: HexCoord, player: Player) -> Token {
+        let mut builder = self.token_builder();
         builder.ring(player).coord(c).z_value(1);
-        if self.phase == Phase::RemoveRing {
-            builder.remove_hover_color().state(ShapeState::Hoverable);
+        if self.phase == Phase::RemoveRing && self.controls(player) {
+            builder.remove_target();
         };
-        self.controller.add_element(Box::new(builder.build()));
+        builder.build()
+    }
+
+    fn add_ring_element(&mut self, c: HexCoord, player: Player) {
+        let token = self.ring_token(c, player);
+        self.controller.add_element(Box::new(token));
     }
 
     fn add_marker_element(&mut self, c: HexCoord, player: Player, state: &State) {
-        let runs = state.current_player_runs();
-        if self.phase == Phase::RemoveRun && runs.iter().flatten().find(|&x| *x == c).is_some() {
+        // markers in a run are drawn by add_run_elements
+        if state.player_runs(player).iter().flatten().any(|x| *x == c) {
             return;
         }
-        let token = TokenBuilder::new()
+        let token = self.token_builder()
             .marker(player)
             .coord(c)
             .z_value(1)
@@ -153,23 +645,46 @@ impl Frontend {
         self.controller.add_element(Box::new(token));
     }
 
-    fn add_run_elements(&mut self, r: &Vec<HexCoord>, z_value: i32) {
-        let mut box_element = Box::new(RunIndicator::from_segment_coords(
-            r[0],
-            *r.last().unwrap(),
-            0.5,
-            z_value,
-        ));
-        box_element.set_coord(r[0]);
-        let box_id = self.controller.add_element(box_element);
+    // Only the current player's runs can be selected, the opponent's are shown
+    // in a different color.
+    fn run_elements(
+        &self,
+        r: &[HexCoord],
+        player: Player,
+        z_value: i32,
+    ) -> (RunIndicator, Vec<Token>) {
+        let color = if player == self.current_player {
+            BLACK
+        } else {
+            DARKPURPLE
+        };
+        let mut indicator =
+            RunIndicator::from_segment_coords(r[0], *r.last().unwrap(), 0.5, z_value)
+                .with_color(color);
+        let mut state = ShapeState::Visible;
+        if self.controls(player) {
+            indicator.set_run(r.to_vec());
+            state = ShapeState::Hoverable;
+        }
+        let tokens = r
+            .iter()
+            .map(|c| {
+                self.token_builder()
+                    .marker(player)
+                    .coord(*c)
+                    .z_value(1)
+                    .state(state)
+                    .build()
+            })
+            .collect();
+        (indicator, tokens)
+    }
+
+    fn add_run_elements(&mut self, r: &[HexCoord], player: Player, z_value: i32) {
+        let (indicator, tokens) = self.run_elements(r, player, z_value);
+        let box_id = self.controller.add_element(Box::new(indicator));
         self.run_bboxes.push(box_id);
-        for c in r {
-            let token = TokenBuilder::new()
-                .marker(self.current_player)
-                .coord(*c)
-                .z_value(1)
-                .state(ShapeState::Hoverable)
-                .build();
+        for token in tokens {
             let marker_id = self.controller.add_element_inactive(Box::new(token));
             self.controller.add_subscriber(box_id, marker_id);
         }
@@ -177,6 +692,7 @@ impl Frontend {
 
     fn add_mouse_element(&mut self, mouse_pos: Point) {
         match self.phase {
+            Phase::MoveRing(_) if !self.controls(self.current_player) => {}
             Phase::MoveRing(from) => {
                 println!("Adding mouse element");
                 let mut element =
@@ -188,56 +704,160 @@ impl Frontend {
                     Box::new(AllowedMovesIndicator::new(from.into(), from.into(), -1));
                 self.controller.add_element(element);
             }
-            _ => (),
+            _ => {
+                if let Some(preview) = self.hover_preview() {
+                    self.controller.add_element(Box::new(preview));
+                }
+            }
         }
     }
 
+    // Ghost of the piece to place, or a red highlight of what a click removes.
+    fn hover_preview(&self) -> Option<HoverPreview> {
+        let player = self.current_player;
+        if !self.is_human(player) {
+            return None;
+        }
+        let mut builder = self.token_builder();
+        let mut color = player_color(player);
+        match self.phase {
+            Phase::PlaceRing => builder.ring(player),
+            Phase::PlaceMarker => builder.marker(player),
+            Phase::RemoveRun => {
+                color = RED;
+                builder.marker(player)
+            }
+            Phase::RemoveRing => {
+                color = RED;
+                builder.ring(player)
+            }
+            _ => return None,
+        };
+        color.a = 0.5;
+        let token = builder.z_value(10).build();
+        Some(HoverPreview::new(token, color))
+    }
+
+    // Slot of the trophy ring that brought `player` to `points`.
+    fn trophy_slot(&self, player: Player, points: usize) -> Point {
+        let slots = match player {
+            Player::White => &self.white_ring_slots,
+            Player::Black => &self.black_ring_slots,
+        };
+        slots[points.saturating_sub(1).min(slots.len() - 1)]
+    }
+
+    // Rings removed by the last move, flying from the board to their slot.
+    fn trophy_flights(&self, state: &State) -> Vec<(Player, HexCoord, Point)> {
+        state
+            .last_state_change
+            .iter()
+            .filter_map(|sc| match sc {
+                StateChange::RingRemoved(player, c) => Some((
+                    *player,
+                    *c,
+                    self.trophy_slot(*player, state.get_score(player)),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The newest trophy of a player with a flying ring is drawn by its animation.
     fn add_won_rings(&mut self, state: &State) {
-        for i in 0..state.points_black {
-            let pt = self.black_ring_slots[i];
-            let token = TokenBuilder::new()
-                .ring(Player::Black)
-                .pos(pt)
-                .z_value(1)
-                .build();
-            self.controller.add_element(Box::new(token));
-        }
-        for i in 0..state.points_white {
-            let pt = self.white_ring_slots[i];
-            let token = TokenBuilder::new()
-                .ring(Player::White)
-                .pos(pt)
-                .z_value(1)
-                .build();
-            self.controller.add_element(Box::new(token));
+        let flying: Vec<Player> = self
+            .trophy_flights(state)
+            .iter()
+            .map(|(player, _, _)| *player)
+            .collect();
+        for player in Player::all() {
+            let mut points = state.get_score(&player);
+            if flying.contains(&player) {
+                points = points.saturating_sub(1);
+            }
+            for i in 1..=points {
+                let token = self.token_builder()
+                    .ring(player)
+                    .pos(self.trophy_slot(player, i))
+                    .z_value(1)
+                    .build();
+                self.controller.add_element(Box::new(token));
+            }
+        }
+    }
+
+    fn placed_token(&self, token: Token) -> Box<dyn Element> {
+        if !self.animation_config.enabled {
+            return Box::new(token);
+        }
+        let animation = PlaceAnimation::new_box(token.shape_type.clone());
+        Box::new(AnimatedToken::new(token, animation))
+    }
+
+    fn ring_move_token(
+        &self,
+        player: Player,
+        from: HexCoord,
+        to: HexCoord,
+    ) -> Option<AnimatedToken> {
+        if self.is_human(player) && !self.animate_own_moves {
+            return None;
+        }
+        let token = self.token_builder()
+            .ring(player)
+            .coord(from)
+            .z_value(1)
+            .animate(MoveAnimation::new_box(Point::from(from), Point::from(to)));
+        Some(token)
+    }
+
+    // Removed markers pop, removed rings fly to their trophy slot.
+    fn removal_token(&self, sc: &StateChange, state: &State) -> Option<AnimatedToken> {
+        match sc {
+            StateChange::MarkerRemoved(player, c) => Some(
+                self.token_builder()
+                    .marker(*player)
+                    .coord(*c)
+                    .z_value(1)
+                    .animate(RemoveAnimation::new_box(1.2)),
+            ),
+            StateChange::RingRemoved(player, c) => {
+                let to_pt = self.trophy_slot(*player, state.get_score(player));
+                Some(
+                    self.token_builder()
+                        .ring(*player)
+                        .coord(*c)
+                        .z_value(1)
+                        .animate(MoveAnimation::new_box(Point::from(*c), to_pt)),
+                )
+            }
+            _ => None,
         }
     }
 
     fn create_animations(&mut self, state: &State) -> HashSet<HexCoord> {
         let mut skip_coords = HashSet::new();
+        let ring_from = state.last_state_change().iter().find_map(|sc| match sc {
+            StateChange::RingMoved(_, from, _) => Some(*from),
+            _ => None,
+        });
         for sc in &state.last_state_change() {
             let token: Option<Box<dyn Element>> = match sc {
                 StateChange::RingPlaced(player, c) => {
                     skip_coords.insert(*c);
-                    let token = TokenBuilder::new()
+                    let token = self.token_builder()
                         .coord(*c)
                         .ring(*player)
                         .z_value(1)
                         .build();
-                    Some(Box::new(token))
+                    Some(self.placed_token(token))
                 }
                 StateChange::RingMoved(player, from, to) => {
-                    if player == &Player::Black {
+                    let token = self.ring_move_token(*player, *from, *to);
+                    if token.is_some() {
                         skip_coords.insert(*to);
-                        let token = TokenBuilder::new()
-                            .ring(*player)
-                            .coord(*from)
-                            .z_value(1)
-                            .animate(MoveAnimation::new_box(Point::from(*from), Point::from(*to)));
-                        Some(Box::new(token))
-                    } else {
-                        None
                     }
+                    token.map(|t| Box::new(t) as Box<dyn Element>)
                 }
                 StateChange::MarkerFlipped(c) => {
                     skip_coords.insert(*c);
@@ -252,50 +872,32 @@ impl Frontend {
                     } else {
                         BLACK
                     };
-                    let token = TokenBuilder::new()
+                    let token = self.token_builder()
                         .marker(player)
                         .coord(*c)
                         .z_value(1)
-                        .animate(FlipAnimation::new_box(start_color, end_color));
+                        .animate(Box::new(
+                            FlipAnimation::new(start_color, end_color)
+                                .with_delay(ring_from.map_or(0., |from| flip_delay(from, *c))),
+                        ));
                     Some(Box::new(token))
                 }
                 StateChange::MarkerPlaced(player, c) => {
                     skip_coords.insert(*c);
-                    let token = TokenBuilder::new()
+                    let token = self.token_builder()
                         .marker(*player)
                         .coord(*c)
                         .z_value(1)
                         .build();
-                    Some(Box::new(token))
+                    Some(self.placed_token(token))
                 }
-                StateChange::MarkerRemoved(player, c) => {
+                StateChange::MarkerRemoved(_, c) | StateChange::RingRemoved(_, c) => {
                     skip_coords.insert(*c);
-                    let token = TokenBuilder::new()
-                        .marker(*player)
-                        .coord(*c)
-                        .z_value(1)
-                        .animate(RemoveAnimation::new_box(1.2));
-                    Some(Box::new(token))
-                }
-                StateChange::RingRemoved(player, c) => {
-                    if state.current_phase == Phase::PlaceMarker {
-                        skip_coords.insert(*c);
-                        let to_pt = if *player == Player::White {
-                            self.white_ring_slots[state.points_white - 1]
-                        } else {
-                            self.black_ring_slots[state.points_black - 1]
-                        };
-
-                        let token = TokenBuilder::new()
-                            .ring(*player)
-                            .coord(*c)
-                            .z_value(1)
-                            .animate(MoveAnimation::new_box(Point::from(*c), to_pt));
-                        Some(Box::new(token))
-                    } else {
-                        None
-                    }
+                    self.removal_token(sc, state)
+                        .map(|t| Box::new(t) as Box<dyn Element>)
                 }
+                // drawn with the other runs by update_from_state
+                StateChange::RunFormed(..) => None,
             };
             token.map(|t| self.controller.add_element(t));
         }
@@ -303,20 +905,41 @@ impl Frontend {
     }
 
     fn update_from_state(&mut self, state: &State) {
+        self.mouse_handler.update();
+        self.build_elements(state, self.mouse_handler.pos());
+    }
+
+    // Replaces all elements with those for `state`, without reading any input.
+    fn build_elements(&mut self, state: &State, mouse_pos: Point) {
         self.current_player = state.current_player;
         self.phase = state.current_phase;
 
         self.legal_moves = state.legal_moves();
+        self.move_summary = if state.last_state_change.is_empty() {
+            None
+        } else {
+            Some(state.last_move_summary().to_string())
+        };
+        self.banner = phase_banner(state, &self.strings);
+        self.run_preview = None;
+        self.context_note = None;
+        self.selected_run = None;
+        self.selected_ring = None;
+        self.scores = state.scores();
+        self.markers_remaining = state.board.markers_remaining();
+        let to_move = state.resolving_player().unwrap_or(state.current_player);
+        if to_move != self.to_move && self.animation_config.enabled {
+            self.turn_change = Some(TurnChangeAnimation::new());
+        }
+        self.to_move = to_move;
 
         self.controller.clear_all();
 
         self.add_legal_move_highlights(state);
 
-        let runs = state.current_player_runs();
-
         let skip_coords = self.create_animations(state);
 
-        for player in [Player::White, Player::Black] {
+        for player in Player::all() {
             state.board.player_rings(player).for_each(|c| {
                 if !skip_coords.contains(c) {
                     self.add_ring_element(*c, player);
@@ -329,15 +952,19 @@ impl Frontend {
                 }
             }
         }
-        for (i, r) in runs.iter().enumerate() {
-            self.add_run_elements(r, (i + 3) as i32);
+        let mut z_value = 3;
+        for player in [self.current_player, self.current_player.other()] {
+            for r in state.player_runs(player) {
+                self.add_run_elements(r, player, z_value);
+                z_value += 1;
+            }
         }
 
         self.add_won_rings(&state);
+        self.controller
+            .add_element(Box::new(CursorElement::new(self.cursor, 5)));
 
-        self.mouse_handler.update();
-        let mouse_event = self.mouse_handler.has_message(None);
-        self.add_mouse_element(mouse_event.pos);
+        self.add_mouse_element(mouse_pos);
     }
 }
 
@@ -346,8 +973,13 @@ impl View for Frontend {
         self.update_request = true;
     }
 
+    fn set_human_player(&mut self, player: Option<Player>) {
+        self.human_player = player;
+    }
+
     fn tick(&mut self, state: &State) -> UiAction {
         self.ui_actions.clear();
+        let time = now();
 
         if self.ui_status == UiStatus::Idle && self.update_request {
             println!("PDATED STATE");
@@ -356,39 +988,66 @@ impl View for Frontend {
         }
         clear_background(LIGHTGRAY);
 
+        self.fit_to_screen(screen_width() as u32, screen_height() as u32);
         self.set_camera();
 
         self.draw_grid();
 
         self.mouse_handler.update();
         let mouse_event = self.mouse_handler.has_message(Some(&self.legal_moves));
-        self.controller.schedule_event(Event::Mouse(mouse_event));
-
-        self.controller.handle_events();
+        self.tooltip = match (&self.context_note, mouse_event.legal_move_coord) {
+            (Some((c, note)), Some(coord)) if *c == coord => Some(note.clone()),
+            (_, coord) => coord.and_then(|c| {
+                move_tooltip(&self.legal_moves, &state.board, c, &self.strings)
+            }),
+        };
+        self.hover_targets = match (self.phase, mouse_event.legal_move_coord) {
+            (Phase::PlaceMarker, Some(c)) if self.is_human(self.current_player) => {
+                state.ring_destinations(&c)
+            }
+            _ => vec![],
+        };
+        let hovered = mouse_event.legal_move_coord;
+        if self.run_preview.as_ref().map(|(c, _)| *c) != hovered {
+            self.run_preview = hovered.map(|c| (c, prospective_runs(state, c)));
+        }
+        if frame_dirty(&mouse_event, self.controller.animations_pending()) {
+            self.controller.schedule_event(Event::Mouse(mouse_event.clone()));
+        }
+        self.update_cursor(&state.board);
 
-        self.controller.render();
+        if self.controller.has_pending_events() {
+            self.controller.handle_events();
+            self.controller.render();
+        } else {
+            self.controller.render_unchanged();
+        }
+        self.controller.tick_animations(time);
+        if let Some(animation) = self.turn_change.as_mut() {
+            animation.tick(time);
+        }
+        if self.turn_change.is_some_and(|a| a.finished()) {
+            self.turn_change = None;
+        }
+        self.draw_hover_targets();
+        self.draw_run_preview();
+        self.draw_selected_run();
+        self.draw_selected_ring();
+        self.draw_move_summary();
+        self.draw_tooltip();
+        self.draw_debug_overlay();
         self.ui_actions = self.controller.get_actions();
-        self.update_user_actions();
+        self.update_user_actions(&mouse_event);
+        self.confirm_ring_clicks();
 
-        if self
-            .ui_actions
-            .iter()
-            .filter(|&a| a == &UiAction::AnimationInProgress)
-            .count()
-            != 0
-        {
+        if self.controller.animations_pending() {
             self.ui_status = UiStatus::Busy;
             return UiAction::Busy;
         } else {
             self.ui_status = UiStatus::Idle;
         }
         //println!("{:?}", self.ui_status);
-        self.ui_actions.retain(|a| match a {
-            UiAction::ActionAtCoord(_) | UiAction::Undo => true,
-            _ => false,
-        });
-
-        self.ui_actions.pop().unwrap_or(UiAction::NoAction)
+        frame_action(&self.ui_actions)
     }
 
     // Idle -> tick --> None -- no update
@@ -400,3 +1059,513 @@ impl View for Frontend {
 
     fn set_interactive(&mut self, flag: bool) {}
 }
+
+#[cfg(test)]
+mod test {
+    use crate::frontend::animation::Animation;
+    use crate::frontend::events::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_remove_ring_phase_styles_removable_rings() {
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        frontend.current_player = Player::White;
+        let c = HexCoord::new(1, 1);
+
+        frontend.phase = Phase::PlaceMarker;
+        let ring = frontend.ring_token(c, Player::White);
+        assert_eq!(ring.outline_color(), None);
+
+        frontend.phase = Phase::RemoveRing;
+        let mut ring = frontend.ring_token(c, Player::White);
+        assert_eq!(ring.outline_color(), Some(ORANGE));
+        assert_eq!(ring.hover_color(), RED);
+
+        ring.update(&Message::MouseEntered);
+        assert_eq!(ring.color(), RED);
+        ring.update(&Message::MouseLeft);
+        assert_eq!(ring.color(), WHITE);
+
+        // the opponent's rings are not removable
+        let ring = frontend.ring_token(c, Player::Black);
+        assert_eq!(ring.outline_color(), None);
+    }
+
+    #[test]
+    fn test_run_elements_use_run_owner() {
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        frontend.current_player = Player::White;
+        let white_run: Vec<HexCoord> = (-2..3).map(|y| HexCoord::new(0, y)).collect();
+        let black_run: Vec<HexCoord> = (-2..3).map(|y| HexCoord::new(2, y)).collect();
+
+        let (indicator, tokens) = frontend.run_elements(&white_run, Player::White, 3);
+        assert_eq!(indicator.color(), BLACK);
+        assert_eq!(tokens.len(), 5);
+        assert!(tokens.iter().all(|t| t.color() == WHITE));
+
+        let (indicator, tokens) = frontend.run_elements(&black_run, Player::Black, 4);
+        assert_eq!(indicator.color(), DARKPURPLE);
+        assert!(tokens.iter().all(|t| t.color() == BLACK));
+
+        // only the current player's run can be clicked, and it names the whole run
+        let (mut indicator, _) = frontend.run_elements(&white_run, Player::White, 3);
+        assert_eq!(
+            indicator.update(&Message::MouseClicked(white_run[0])),
+            Some(UiAction::RemoveRunAt(white_run.clone()))
+        );
+        let (mut indicator, _) = frontend.run_elements(&black_run, Player::Black, 4);
+        assert_eq!(indicator.update(&Message::MouseClicked(black_run[0])), None);
+    }
+
+    #[test]
+    fn test_build_elements_covers_board() {
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        let mut state = State::new(Board::new());
+        for (piece, c) in [
+            (Piece::Ring(Player::White), (0, 0)),
+            (Piece::Ring(Player::White), (2, 1)),
+            (Piece::Ring(Player::Black), (-3, -3)),
+            (Piece::Marker(Player::White), (1, 0)),
+            (Piece::Marker(Player::White), (1, 1)),
+            (Piece::Marker(Player::Black), (-1, 0)),
+        ] {
+            state.board.place_unchecked(&piece, &HexCoord::from(c));
+        }
+        state.set_phase(Phase::PlaceMarker);
+        state.points_black = 1;
+
+        frontend.build_elements(&state, Point(0., 0.));
+        // a field marker per legal move, 6 pieces, 1 won ring, cursor and preview
+        assert_eq!(frontend.controller.element_count(), 2 + 6 + 1 + 2);
+
+        frontend.build_elements(&State::new(Board::new()), Point(0., 0.));
+        // the grid of free fields, cursor and preview
+        assert_eq!(frontend.controller.element_count(), 3);
+    }
+
+    #[test]
+    fn test_turn_change_starts_indicator_animation() {
+        let mut state = State::new(Board::new());
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        frontend.build_elements(&state, Point(0., 0.));
+        assert!(frontend.turn_change.is_none());
+
+        state.current_player = Player::Black;
+        frontend.build_elements(&state, Point(0., 0.));
+        let mut animation = frontend.turn_change.unwrap();
+        animation.tick(10.);
+        assert_eq!(animation.progress(), 0.);
+        animation.tick(10.3);
+        assert!(!animation.finished());
+        assert!(animation.progress() > 0. && animation.progress() < 1.);
+        animation.tick(10.7);
+        assert!(animation.finished());
+
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.)
+            .with_animation_config(AnimationConfig { enabled: false });
+        frontend.build_elements(&state, Point(0., 0.));
+        assert!(frontend.turn_change.is_none());
+    }
+
+    #[test]
+    fn test_ring_removal_needs_second_click() {
+        let mut frontend =
+            Frontend::new(&Board::new(), 1024, 1024, 1., 1.).with_ring_removal_confirm(true);
+        frontend.phase = Phase::RemoveRing;
+        let (c, d) = (HexCoord::new(1, 1), HexCoord::new(2, 0));
+        let click = |frontend: &mut Frontend, coord| {
+            frontend.ui_actions = vec![UiAction::ActionAtCoord(coord)];
+            frontend.confirm_ring_clicks();
+            frame_action(&frontend.ui_actions)
+        };
+
+        assert_eq!(click(&mut frontend, c), UiAction::NoAction);
+        assert_eq!(frontend.selected_ring, Some(c));
+        // another ring moves the selection
+        assert_eq!(click(&mut frontend, d), UiAction::NoAction);
+        assert_eq!(click(&mut frontend, d), UiAction::ActionAtCoord(d));
+
+        // other phases and the default setting pass clicks through
+        frontend.phase = Phase::PlaceMarker;
+        assert_eq!(click(&mut frontend, c), UiAction::ActionAtCoord(c));
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        frontend.phase = Phase::RemoveRing;
+        assert_eq!(click(&mut frontend, c), UiAction::ActionAtCoord(c));
+    }
+
+    #[test]
+    fn test_tab_cycles_removable_runs() {
+        assert_eq!(cycle_run(None, 0, false), None);
+        assert_eq!(cycle_run(None, 3, false), Some(0));
+        assert_eq!(cycle_run(None, 3, true), Some(2));
+        assert_eq!(cycle_run(Some(2), 3, false), Some(0));
+        assert_eq!(cycle_run(Some(0), 3, true), Some(2));
+        assert_eq!(cycle_run(Some(1), 3, false), Some(2));
+
+        // six white markers in a line hold two overlapping runs
+        let mut state = State::new(Board::new());
+        for y in -3..3 {
+            state
+                .board
+                .place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, y));
+        }
+        state.set_phase(Phase::RemoveRun);
+        state.compute_runs();
+        let runs = removable_runs(&state.legal_moves());
+        assert_eq!(runs.len(), 2);
+
+        let mut selected = None;
+        for expected in [0, 1, 0] {
+            selected = cycle_run(selected, runs.len(), false);
+            assert_eq!(
+                selected_run_action(&runs, selected),
+                Some(UiAction::RemoveRunAt(runs[expected].clone()))
+            );
+        }
+        assert_eq!(selected_run_action(&runs, None), None);
+    }
+
+    #[test]
+    fn test_flips_start_in_ring_order() {
+        let from = HexCoord::new(0, -2);
+        let colors: Vec<f32> = (-1..3)
+            .map(|y| {
+                let c = HexCoord::new(0, y);
+                let mut flip = FlipAnimation::new(WHITE, BLACK).with_delay(flip_delay(from, c));
+                let mut token = Token::new_marker_at_coord(c, Player::White, 1);
+                flip.tick(0.);
+                flip.tick(0.12);
+                flip.apply(&mut token);
+                token.color().r
+            })
+            .collect();
+        assert_eq!(flip_delay(from, HexCoord::new(0, -1)), 0.);
+        // the marker next to the ring's start is furthest towards black
+        for pair in colors.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_removed_rings_fly_to_trophy_slot() {
+        let frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        let c = HexCoord::new(1, 1);
+        for phase in [Phase::PlaceMarker, Phase::RemoveRun, Phase::PlayerWon(Player::Black)] {
+            let mut state = State::new(Board::new());
+            state.set_phase(phase);
+            state.points_black = 2;
+            state.last_state_change = vec![StateChange::RingRemoved(Player::Black, c)];
+
+            let flights = frontend.trophy_flights(&state);
+            assert_eq!(flights, vec![(Player::Black, c, frontend.black_ring_slots[1])]);
+        }
+
+        let mut state = State::new(Board::new());
+        state.points_white = 1;
+        state.last_state_change = vec![StateChange::RingRemoved(Player::White, c)];
+        let flights = frontend.trophy_flights(&state);
+        assert_eq!(flights, vec![(Player::White, c, frontend.white_ring_slots[0])]);
+    }
+
+    #[test]
+    fn test_hover_preview_matches_phase() {
+        let mut frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        frontend.set_human_player(Some(Player::Black));
+        frontend.current_player = Player::Black;
+        let c = HexCoord::new(0, 1);
+        let hover = Event::Mouse(MouseEvent {
+            pos: Point::from(c),
+            last_pos: Point::from(c),
+            coord: Some(c),
+            legal_move_coord: Some(c),
+            left_clicked: false,
+            right_clicked: false,
+        });
+
+        for (phase, ring, red) in [
+            (Phase::PlaceRing, true, false),
+            (Phase::PlaceMarker, false, false),
+            (Phase::RemoveRun, false, true),
+            (Phase::RemoveRing, true, true),
+        ] {
+            frontend.phase = phase;
+            let mut preview = frontend.hover_preview().unwrap();
+            for msg in preview.handle_event(&hover) {
+                preview.update(&msg);
+            }
+            assert!(preview.is_visible());
+            let token = preview.token();
+            assert_eq!(matches!(token.shape_type, TokenType::Ring(..)), ring);
+            let mut color = if red { RED } else { BLACK };
+            color.a = 0.5;
+            assert_eq!(token.color(), color);
+            assert_eq!(token.player(), Some(Player::Black));
+        }
+
+        frontend.phase = Phase::MoveRing(c);
+        assert!(frontend.hover_preview().is_none());
+
+        // nothing to preview while the AI is to move, in hot seat mode both sides get one
+        frontend.phase = Phase::PlaceMarker;
+        frontend.current_player = Player::White;
+        assert!(frontend.hover_preview().is_none());
+        frontend.set_human_player(None);
+        assert!(frontend.hover_preview().is_some());
+    }
+
+    #[test]
+    fn test_own_ring_move_animation() {
+        let (from, to) = (HexCoord::new(0, 0), HexCoord::new(0, 3));
+        let frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        assert!(frontend.ring_move_token(Player::White, from, to).is_none());
+        assert!(frontend.ring_move_token(Player::Black, from, to).is_some());
+
+        let mut black = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        black.set_human_player(Some(Player::Black));
+        assert!(black.ring_move_token(Player::White, from, to).is_some());
+        assert!(black.ring_move_token(Player::Black, from, to).is_none());
+
+        let frontend = frontend.with_own_move_animation(true);
+        let token = frontend.ring_move_token(Player::White, from, to).unwrap();
+        assert!(token.is_animated());
+        assert_eq!(token.pos(), Point::from(from));
+    }
+
+    #[test]
+    fn test_dual_runs_resolved_mover_first() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, -2);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &from);
+        let black_ring = HexCoord::new(-3, -1);
+        state.board.place_unchecked(&Piece::Ring(Player::Black), &black_ring);
+        let white_ring = HexCoord::new(-3, 1);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &white_ring);
+        for y in -1..3 {
+            state.board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, y));
+        }
+        for y in -2..3 {
+            state.board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(3, y));
+        }
+        state.compute_runs();
+        state.set_phase(Phase::PlaceMarker);
+        let strings = Strings::default();
+        assert_eq!(phase_banner(&state, &strings), None);
+
+        let mut banners = vec![];
+        let clicks = [from, HexCoord::new(1, -2)];
+        state.play_clicks(&clicks).unwrap();
+        banners.extend(phase_banner(&state, &strings));
+        let run = state.player_runs(Player::White)[0].clone();
+        Action::from(RemoveRun { run_idx: 0, coord: run[0], run }).execute(&mut state);
+        banners.extend(phase_banner(&state, &strings));
+        state.execute_for_coord(&white_ring).unwrap();
+        banners.extend(phase_banner(&state, &strings));
+        let run = state.player_runs(Player::Black)[0].clone();
+        Action::from(RemoveRun { run_idx: 0, coord: run[0], run }).execute(&mut state);
+        banners.extend(phase_banner(&state, &strings));
+
+        assert_eq!(
+            banners,
+            vec![
+                "White removes a run.",
+                "White removes a ring.",
+                "Black removes a run.",
+                "Black removes a ring.",
+            ]
+        );
+
+        state.set_phase(Phase::PlayerWon(Player::Black));
+        assert_eq!(phase_banner(&state, &strings).as_deref(), Some("Black wins!"));
+    }
+
+    #[test]
+    fn test_removed_rings_never_pop() {
+        let frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        let mut state = State::new(Board::new());
+        state.points_white = 1;
+        let c = HexCoord::new(1, 1);
+
+        let ring_removed = StateChange::RingRemoved(Player::White, c);
+        let mut ring = frontend.removal_token(&ring_removed, &state).unwrap();
+        let nominal = ring.token().shape_type.clone();
+        ring.update(&Message::Tick(0.));
+        ring.update(&Message::Tick(0.1));
+        assert!(matches!(
+            (ring.token().shape_type.clone(), nominal),
+            (TokenType::Ring(a, b), TokenType::Ring(c, d)) if a == c && b == d
+        ));
+        assert_ne!(ring.pos(), Point::from(c));
+
+        let marker_removed = StateChange::MarkerRemoved(Player::White, c);
+        let mut marker = frontend.removal_token(&marker_removed, &state).unwrap();
+        marker.update(&Message::Tick(0.));
+        marker.update(&Message::Tick(0.05));
+        assert!(matches!(marker.token().shape_type, TokenType::Marker(r) if r != 0.2));
+        assert_eq!(marker.pos(), Point::from(c));
+
+        let flipped = StateChange::MarkerFlipped(c);
+        assert!(frontend.removal_token(&flipped, &state).is_none());
+    }
+
+    #[test]
+    fn test_frame_dirty() {
+        let still = MouseEvent {
+            pos: Point(0.5, 0.5),
+            last_pos: Point(0.5, 0.5),
+            coord: None,
+            legal_move_coord: None,
+            left_clicked: false,
+            right_clicked: false,
+        };
+        assert!(!frame_dirty(&still, false));
+        assert!(frame_dirty(&still, true));
+
+        let moved = MouseEvent {
+            pos: Point(0.6, 0.5),
+            ..still.clone()
+        };
+        assert!(frame_dirty(&moved, false));
+
+        let clicked = MouseEvent {
+            left_clicked: true,
+            ..still
+        };
+        assert!(frame_dirty(&clicked, false));
+    }
+
+    #[test]
+    fn test_ring_destination_dots() {
+        let frontend = Frontend::new(&Board::new(), 1024, 1024, 1., 1.);
+        let mut board = Board::new();
+        let from = HexCoord::new(0, 0);
+        board.place_unchecked(&Piece::Marker(Player::White), &from);
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(0, 2));
+        board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(1, 1));
+
+        let dots = frontend.ring_destination_dots(from, &board);
+        assert_eq!(dots.len(), board.ring_targets(&from).len());
+
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-1, 0));
+        let dots = frontend.ring_destination_dots(from, &board);
+        assert_eq!(dots.len(), board.ring_targets(&from).len());
+    }
+
+    #[test]
+    fn test_move_tooltip() {
+        let mut board = Board::new();
+        let from = HexCoord::new(0, 0);
+        for y in 1..=2 {
+            board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, y));
+        }
+        let to = HexCoord::new(0, 3);
+        let moves = vec![Action::from(MoveRing {
+            from,
+            to,
+            player: Player::White,
+        })];
+        let strings = Strings::default();
+        assert_eq!(
+            move_tooltip(&moves, &board, to, &strings).as_deref(),
+            Some("Move ring here, flips 2 markers")
+        );
+        assert_eq!(move_tooltip(&moves, &board, HexCoord::new(0, 4), &strings), None);
+        let german = Strings {
+            tooltip_flips: "Ring hierher, dreht {count} Steine".to_owned(),
+            ..Strings::default()
+        };
+        assert_eq!(
+            move_tooltip(&moves, &board, to, &german).as_deref(),
+            Some("Ring hierher, dreht 2 Steine")
+        );
+
+        let moves = vec![Action::from(PlaceRing { coord: from })];
+        assert_eq!(
+            move_tooltip(&moves, &board, from, &strings).as_deref(),
+            Some("Place ring")
+        );
+    }
+
+    #[test]
+    fn test_undo_has_own_binding() {
+        let keys = |keys: Vec<KeyCode>| FrameInput {
+            keys,
+            ..FrameInput::default()
+        };
+        assert_eq!(input_actions(&keys(vec![UNDO_KEY])), vec![UiAction::Undo]);
+        assert_eq!(
+            input_actions(&keys(vec![KeyCode::Escape])),
+            vec![UiAction::CancelMove]
+        );
+        assert!(input_actions(&keys(vec![KeyCode::Z])).is_empty());
+
+        let right_click = FrameInput {
+            right_clicked: true,
+            ..FrameInput::default()
+        };
+        assert!(input_actions(&right_click).is_empty());
+    }
+
+    #[test]
+    fn test_ring_removal_note() {
+        let strings = Strings::default();
+        assert_eq!(ring_removal_note(1, &strings), "Scores point 2 of 3");
+        assert_eq!(ring_removal_note(2, &strings), "Removing this ring wins the game");
+        let german = Strings {
+            note_wins: "Dieser Ring gewinnt".to_owned(),
+            note_scores: "Punkt {point} von {total}".to_owned(),
+            ..Strings::default()
+        };
+        assert_eq!(ring_removal_note(1, &german), "Punkt 2 von 3");
+        assert_eq!(ring_removal_note(2, &german), "Dieser Ring gewinnt");
+    }
+
+    #[test]
+    fn test_prospective_runs() {
+        let mut state = State::new(Board::new());
+        let from = HexCoord::new(0, 0);
+        state.board.place_unchecked(&Piece::Ring(Player::White), &from);
+        state.board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(-3, -3));
+        state.board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(0, 1));
+        for x in 1..5 {
+            state.board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(x, 1));
+        }
+        state.set_phase(Phase::PlaceMarker);
+        assert!(prospective_runs(&state, HexCoord::new(0, 2)).is_empty());
+        state.execute_for_coord(&from).unwrap();
+
+        // jumping north flips the black marker and completes the row
+        let row: Vec<HexCoord> = (0..5).map(|x| HexCoord::new(x, 1)).collect();
+        assert_eq!(prospective_runs(&state, HexCoord::new(0, 2)), vec![row]);
+        assert!(prospective_runs(&state, HexCoord::new(0, -1)).is_empty());
+        // not a ring target
+        assert!(prospective_runs(&state, HexCoord::new(2, 1)).is_empty());
+        // the preview doesn't touch the game
+        assert!(matches!(state.current_phase, Phase::MoveRing(_)));
+    }
+
+    #[test]
+    fn test_frame_action_priority() {
+        let coord = UiAction::ActionAtCoord(HexCoord::new(1, 1));
+        assert_eq!(
+            frame_action(&[coord.clone(), UiAction::Undo]),
+            UiAction::Undo
+        );
+        assert_eq!(
+            frame_action(&[UiAction::Undo, coord.clone()]),
+            UiAction::Undo
+        );
+        assert_eq!(
+            frame_action(&[
+                coord.clone(),
+                UiAction::ActionAtCoord(HexCoord::new(2, 2))
+            ]),
+            coord
+        );
+        assert_eq!(
+            frame_action(&[UiAction::AnimationFinished]),
+            UiAction::NoAction
+        );
+        assert_eq!(frame_action(&[]), UiAction::NoAction);
+    }
+}
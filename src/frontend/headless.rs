@@ -0,0 +1,61 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::core::entities::Player;
+use crate::core::game::{UiAction, View};
+use crate::core::state::State;
+
+// A View without graphics: hands out a scripted sequence of UiActions, one per
+// tick, and counts update requests. Used to drive Game in tests.
+pub struct HeadlessView {
+    actions: VecDeque<UiAction>,
+    update_requests: Rc<Cell<usize>>,
+    human_player: Rc<Cell<Option<Player>>>,
+    interactive: bool,
+}
+
+impl HeadlessView {
+    pub fn new(actions: Vec<UiAction>) -> Self {
+        HeadlessView {
+            actions: actions.into(),
+            update_requests: Rc::new(Cell::new(0)),
+            human_player: Rc::new(Cell::new(None)),
+            interactive: true,
+        }
+    }
+
+    // Shared counter, still readable after the view has been boxed into a Game.
+    pub fn update_requests(&self) -> Rc<Cell<usize>> {
+        self.update_requests.clone()
+    }
+
+    // Last side passed to set_human_player(), shared like update_requests().
+    pub fn human_player(&self) -> Rc<Cell<Option<Player>>> {
+        self.human_player.clone()
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+}
+
+impl View for HeadlessView {
+    fn invalid_action(&self) {}
+
+    fn request_update(&mut self) {
+        self.update_requests.set(self.update_requests.get() + 1);
+    }
+
+    fn set_interactive(&mut self, flag: bool) {
+        self.interactive = flag;
+    }
+
+    fn set_human_player(&mut self, player: Option<Player>) {
+        self.human_player.set(player);
+    }
+
+    fn tick(&mut self, _state: &State) -> UiAction {
+        self.actions.pop_front().unwrap_or(UiAction::NoAction)
+    }
+}
@@ -6,4 +6,8 @@ pub mod mouse;
 pub mod controller;
 pub mod animation;
 pub mod events;
-pub mod elements;
\ No newline at end of file
+pub mod elements;
+pub mod headless;
+pub mod svg;
+pub mod scoreboard;
+pub mod strings;
\ No newline at end of file
@@ -1,9 +1,9 @@
-use macroquad::prelude::{is_mouse_button_pressed, mouse_position, MouseButton};
+use macroquad::prelude::{is_mouse_button_down, mouse_position, MouseButton};
 
 use crate::core::command::Command;
 use crate::{
     common::coord::{HexCoord, Point},
-    core::actions::Action,
+    core::{actions::Action, board::Board},
 };
 
 use super::events::Message;
@@ -37,81 +37,225 @@ where
     msg
 }
 
+// Fingers are less precise than a mouse pointer, so touch input picks fields
+// from further away. Fields are one unit apart, both radii stay below half of that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InputMode {
+    #[default]
+    Mouse,
+    Touch,
+}
+
+impl InputMode {
+    // Clicks further away from every field don't select one.
+    pub fn pick_radius(&self) -> f32 {
+        match self {
+            InputMode::Mouse => 0.3,
+            InputMode::Touch => 0.45,
+        }
+    }
+}
+
 pub struct MouseHandler {
+    board: Board,
     pos: Point,
     last_pos: Point,
     height: f32,
     width: f32,
     pixel_height: u32,
     pixel_width: u32,
+    left_down: bool,
+    right_down: bool,
+    // registered but not yet reported by has_message()
+    left_click: bool,
+    right_click: bool,
+    input_mode: InputMode,
 }
 
 impl MouseHandler {
-    pub fn new(width: f32, height: f32, pixel_width: u32, pixel_height: u32) -> Self {
+    pub fn new(
+        board: &Board,
+        width: f32,
+        height: f32,
+        pixel_width: u32,
+        pixel_height: u32,
+    ) -> Self {
         Self {
+            board: Board::with_radius(board.hex_radius()),
             width,
             height,
             pixel_height,
             pixel_width,
             pos: Point(0., 0.),
             last_pos: Point(0., 0.),
+            left_down: false,
+            right_down: false,
+            left_click: false,
+            right_click: false,
+            input_mode: InputMode::default(),
         }
     }
 
+    pub fn set_input_mode(&mut self, mode: InputMode) {
+        self.input_mode = mode;
+    }
+
+    pub fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32, pixel_width: u32, pixel_height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixel_width = pixel_width;
+        self.pixel_height = pixel_height;
+    }
+
     pub fn update(&mut self) {
         self.last_pos = self.pos;
         let mp = mouse_position();
         let (x, y) = self.pixels_to_xy(mp.0, mp.1);
         self.pos = Point(x, y);
+        self.register_buttons(
+            is_mouse_button_down(MouseButton::Left),
+            is_mouse_button_down(MouseButton::Right),
+        );
     }
 
-    pub fn has_message(&self, legal_moves: Option<&Vec<Action>>) -> MouseEvent {
-        let left_clicked = is_mouse_button_pressed(MouseButton::Left);
-        let right_clicked = is_mouse_button_pressed(MouseButton::Right);
+    pub fn pos(&self) -> Point {
+        self.pos
+    }
+
+    // A click is a button going down. Holding it over several frames or
+    // updating twice in a frame doesn't register another one.
+    fn register_buttons(&mut self, left_down: bool, right_down: bool) {
+        self.left_click |= left_down && !self.left_down;
+        self.right_click |= right_down && !self.right_down;
+        self.left_down = left_down;
+        self.right_down = right_down;
+    }
+
+    // Consumes the registered clicks: only the first event after a click reports it.
+    pub fn has_message(&mut self, legal_moves: Option<&Vec<Action>>) -> MouseEvent {
+        let left_clicked = std::mem::take(&mut self.left_click);
+        let right_clicked = std::mem::take(&mut self.right_click);
 
         MouseEvent {
             last_pos: self.last_pos,
             pos: self.pos,
-            coord: self.to_coord(Some(0.09)),
-            legal_move_coord: legal_moves.and_then(|l| self.to_legal_field(l, Some(0.09))),
+            coord: self.to_coord(),
+            legal_move_coord: legal_moves.and_then(|l| self.to_legal_field(l)),
             left_clicked,
             right_clicked,
         }
     }
 
-    pub fn to_coord(&self, max_sq_dist: Option<f32>) -> Option<HexCoord> {
-        let maxd = max_sq_dist.unwrap_or(f32::INFINITY);
-
-        let (coord, sq_dist) = HexCoord::closest_coord_to_point(&self.pos);
-        if sq_dist <= maxd {
-            Some(coord)
-        } else {
-            None
-        }
+    pub fn to_coord(&self) -> Option<HexCoord> {
+        self.board
+            .closest_field_within(self.pos.0, self.pos.1, self.input_mode.pick_radius())
     }
 
-    pub fn to_legal_field(
-        &self,
-        legal_moves: &Vec<Action>,
-        max_sq_dist: Option<f32>,
-    ) -> Option<HexCoord> {
-        let maxd = max_sq_dist.unwrap_or(f32::INFINITY);
-
-        let (coord, sq_dist) = HexCoord::closest_coord_to_point(&self.pos);
-
-        legal_moves
-            .iter()
-            .find(|&a| a.coord() == coord)
-            .and(if sq_dist <= maxd { Some(coord) } else { None })
+    pub fn to_legal_field(&self, legal_moves: &[Action]) -> Option<HexCoord> {
+        self.to_coord()
+            .filter(|c| legal_moves.iter().any(|a| a.coord() == *c))
     }
 
     fn pixels_to_xy(&self, px: f32, py: f32) -> (f32, f32) {
-        let h_ratio = self.pixel_width as f32 / self.width;
-        let w_ratio = self.pixel_height as f32 / self.height;
+        let x_ratio = self.width / self.pixel_width as f32;
+        let y_ratio = self.height / self.pixel_height as f32;
 
         (
-            1. / w_ratio * (px - self.pixel_width as f32 / 2.),
-            -1. / h_ratio * (py - self.pixel_height as f32 / 2.),
+            x_ratio * (px - self.pixel_width as f32 / 2.),
+            -y_ratio * (py - self.pixel_height as f32 / 2.),
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::core::actions::PlaceRing;
+    use crate::core::game::UiAction;
+    use crate::frontend::element::Element;
+    use crate::frontend::elements::field_grid::FieldGridElement;
+    use crate::frontend::events::Event;
+    use crate::frontend::frontend::world_size;
+
+    use super::*;
+
+    #[test]
+    fn test_pixels_to_xy_after_resize() {
+        let board = Board::new();
+        let base = (11., 11.);
+        let (w, h) = world_size(base, 800, 800);
+        let mut handler = MouseHandler::new(&board, w, h, 800, 800);
+        assert_eq!(handler.pixels_to_xy(400., 400.), (0., 0.));
+        assert_eq!(handler.pixels_to_xy(0., 0.), (-5.5, 5.5));
+
+        let (w, h) = world_size(base, 1600, 900);
+        assert_eq!(h, 11.);
+        assert!(w > 11.);
+        handler.resize(w, h, 1600, 900);
+        assert_eq!(handler.pixels_to_xy(800., 450.), (0., 0.));
+        assert_eq!(handler.pixels_to_xy(800., 0.), (0., 5.5));
+        // one world unit is the same number of pixels along both axes
+        let (x, _) = handler.pixels_to_xy(800. + 900. / 11., 450.);
+        assert!((x - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_touch_picks_fields_from_further_away() {
+        let board = Board::new();
+        let c = HexCoord::new(1, 1);
+        let near = Point::from(c) + Point(0.38, 0.);
+        let mut handler = MouseHandler::new(&board, 11., 11., 800, 800);
+        handler.pos = near;
+        assert_eq!(handler.to_coord(), None);
+
+        handler.set_input_mode(InputMode::Touch);
+        assert_eq!(handler.to_coord(), Some(c));
+        let moves = vec![Action::from(PlaceRing { coord: c })];
+        assert_eq!(handler.has_message(Some(&moves)).legal_move_coord, Some(c));
+
+        // the field elements use the same radius
+        let event = |pos| {
+            Event::Mouse(MouseEvent {
+                pos,
+                last_pos: pos,
+                coord: None,
+                legal_move_coord: None,
+                left_clicked: true,
+                right_clicked: false,
+            })
+        };
+        for (mode, hit) in [(InputMode::Mouse, false), (InputMode::Touch, true)] {
+            let grid = FieldGridElement::new(&[c], 0.1, mode.pick_radius(), 1);
+            assert_eq!(!grid.handle_event(&event(near)).is_empty(), hit);
+        }
+    }
+
+    #[test]
+    fn test_held_click_yields_one_action() {
+        let board = Board::new();
+        let mut handler = MouseHandler::new(&board, 11., 11., 800, 800);
+        let c = HexCoord::new(0, 0);
+        let moves = vec![Action::from(PlaceRing { coord: c })];
+        let mut grid = FieldGridElement::new(&[c], 0.1, 0.3, 1);
+
+        let mut actions = vec![];
+        for down in [true, true, false] {
+            handler.register_buttons(down, false);
+            // polled twice per tick, like the frontend does
+            for _ in 0..2 {
+                let event = Event::Mouse(handler.has_message(Some(&moves)));
+                for msg in grid.handle_event(&event) {
+                    actions.extend(grid.update(&msg));
+                }
+            }
+        }
+        assert_eq!(actions, vec![UiAction::ActionAtCoord(c)]);
+
+        // releasing and pressing again is a new click
+        handler.register_buttons(true, false);
+        assert!(handler.has_message(None).left_clicked);
+    }
+}
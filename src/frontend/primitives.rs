@@ -4,6 +4,10 @@ use std::f32::consts::PI;
 
 use crate::{common::coord::{Point, HexCoordF, HexCoord}, core::entities::Player};
 
+// events.rs is the single definition of Event/Message; re-exported here so code
+// importing from primitives sees the same types.
+pub use super::events::{Event, Message};
+
 pub fn build_grid_lines(radius: f32) -> Vec<[HexCoordF; 2]> {
     let dx: f32 = 0.5 * (3. as f32).sqrt();
     let mut res = Vec::new();
@@ -116,3 +120,23 @@ pub fn draw_ring_mesh(x: f32, y: f32, inner: f32, outer: f32, color: Color) {
 
     draw_mesh(&mesh);
 }
+
+#[cfg(test)]
+mod test {
+    use crate::frontend::events;
+
+    use super::*;
+
+    fn takes_primitives_event(event: Event) -> Event {
+        event
+    }
+
+    #[test]
+    fn test_events_and_primitives_share_types() {
+        let event = events::Event::FlipMarker(HexCoord::new(0, 0));
+        assert_eq!(takes_primitives_event(event.clone()), event);
+
+        let msg: Message = events::Message::Tick(0.);
+        assert_eq!(msg, events::Message::Tick(0.));
+    }
+}
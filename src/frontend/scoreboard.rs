@@ -0,0 +1,142 @@
+use macroquad::prelude::*;
+
+use crate::core::entities::Player;
+
+use super::strings::Strings;
+
+const MIN_FONT_SIZE: f32 = 18.;
+const MAX_FONT_SIZE: f32 = 72.;
+// below this many markers left the supply is shown as a warning
+const LOW_MARKER_SUPPLY: usize = 5;
+
+// Screen space positions of the scoreboard, one row per player in
+// Player::all() order and the marker supply above them, anchored to the bottom
+// left corner of the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoreboardLayout {
+    pub font_size: f32,
+    pub rows: [(f32, f32); 2],
+    pub supply: (f32, f32),
+}
+
+// Font size follows the shorter window side so the board stays readable on a
+// stream at any window size.
+pub fn scoreboard_layout(screen_width: f32, screen_height: f32) -> ScoreboardLayout {
+    let font_size = (screen_width.min(screen_height) / 16.).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    let margin = font_size / 2.;
+    let line_height = font_size * 1.2;
+    let bottom = screen_height - margin;
+    ScoreboardLayout {
+        font_size,
+        rows: [(margin, bottom - line_height), (margin, bottom)],
+        supply: (margin, bottom - 2. * line_height),
+    }
+}
+
+pub fn scoreboard_label(strings: &Strings, player: Player, points: usize, to_move: bool) -> String {
+    let indicator = if to_move { ">" } else { " " };
+    format!("{} {}: {}", indicator, strings.player(player), points)
+}
+
+// The label and whether the supply is low enough to warn about.
+pub fn marker_supply_label(strings: &Strings, remaining: usize) -> (String, bool) {
+    let label = strings.markers_left.replace("{count}", &remaining.to_string());
+    (label, remaining < LOW_MARKER_SUPPLY)
+}
+
+// Expects the default (screen space) camera to be set. `sweep` is the progress
+// of a turn change animation over the row of the player to move.
+pub fn draw_scoreboard(
+    strings: &Strings,
+    scores: &[(Player, usize); 2],
+    to_move: Player,
+    markers_remaining: usize,
+    sweep: Option<f32>,
+) {
+    let layout = scoreboard_layout(screen_width(), screen_height());
+    let row = scores.iter().zip(layout.rows).find(|((p, _), _)| *p == to_move);
+    if let (Some(t), Some((_, (x, y)))) = (sweep, row) {
+        let width = 6. * layout.font_size * t;
+        let color = Color::new(0.5, 0., 0., 0.3 * (1. - t));
+        draw_rectangle(x, y - layout.font_size, width, layout.font_size * 1.2, color);
+    }
+    let (label, low) = marker_supply_label(strings, markers_remaining);
+    let color = if low { RED } else { DARKGRAY };
+    draw_text(&label, layout.supply.0, layout.supply.1, layout.font_size, color);
+    for ((player, points), (x, y)) in scores.iter().zip(layout.rows) {
+        let color = if *player == to_move { MAROON } else { DARKGRAY };
+        let label = scoreboard_label(strings, *player, *points, *player == to_move);
+        draw_text(&label, x, y, layout.font_size, color);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layout_scales_with_window() {
+        let small = scoreboard_layout(800., 600.);
+        let large = scoreboard_layout(1920., 1080.);
+        assert!(large.font_size > small.font_size);
+        assert_eq!(small.font_size, 600. / 16.);
+
+        // rows stack upwards from the bottom edge and stay inside the window
+        for (layout, height) in [(small, 600.), (large, 1080.)] {
+            let [(x0, y0), (x1, y1)] = layout.rows;
+            assert_eq!(x0, x1);
+            assert!(y0 < y1 && y1 < height);
+            assert_eq!(layout.supply.0, x0);
+            assert!(layout.supply.1 < y0);
+            assert!(y0 - layout.font_size > 0.);
+        }
+
+        assert_eq!(scoreboard_layout(100., 100.).font_size, MIN_FONT_SIZE);
+        assert_eq!(scoreboard_layout(4000., 3000.).font_size, MAX_FONT_SIZE);
+    }
+
+    #[test]
+    fn test_label_marks_player_to_move() {
+        let strings = Strings::default();
+        assert_eq!(
+            scoreboard_label(&strings, Player::White, 2, true),
+            "> White: 2"
+        );
+        assert_eq!(
+            scoreboard_label(&strings, Player::Black, 0, false),
+            "  Black: 0"
+        );
+    }
+
+    #[test]
+    fn test_marker_supply_label() {
+        let strings = Strings::default();
+        assert_eq!(
+            marker_supply_label(&strings, 51),
+            ("markers left: 51".to_owned(), false)
+        );
+        assert!(!marker_supply_label(&strings, 5).1);
+        assert_eq!(
+            marker_supply_label(&strings, 4),
+            ("markers left: 4".to_owned(), true)
+        );
+        assert!(marker_supply_label(&strings, 0).1);
+    }
+
+    #[test]
+    fn test_label_reads_strings() {
+        let strings = Strings {
+            white: "Blanc".to_owned(),
+            black: "Noir".to_owned(),
+            ..Strings::default()
+        };
+        assert_eq!(
+            scoreboard_label(&strings, Player::White, 1, false),
+            "  Blanc: 1"
+        );
+        assert_eq!(
+            scoreboard_label(&strings, Player::Black, 2, true),
+            "> Noir: 2"
+        );
+    }
+}
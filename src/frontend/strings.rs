@@ -0,0 +1,104 @@
+use crate::core::entities::Player;
+
+// All text the frontend shows, so that the language can be swapped. Templates
+// contain "{player}" where the player's name goes, markers_left and
+// tooltip_flips have "{count}", note_scores has "{point}" and "{total}".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Strings {
+    pub title: String,
+    pub white: String,
+    pub black: String,
+    pub removes_run: String,
+    pub removes_ring: String,
+    pub wins: String,
+    pub draw: String,
+    pub markers_left: String,
+    // tooltips for the legal move under the cursor
+    pub tooltip_place_ring: String,
+    pub tooltip_place_marker: String,
+    pub tooltip_move_ring: String,
+    pub tooltip_flips_one: String,
+    pub tooltip_flips: String,
+    pub tooltip_remove_run: String,
+    pub tooltip_remove_ring: String,
+    // right-click notes on a ring that can be removed
+    pub note_wins: String,
+    pub note_scores: String,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Strings {
+            title: "yinsh".to_owned(),
+            white: "White".to_owned(),
+            black: "Black".to_owned(),
+            removes_run: "{player} removes a run.".to_owned(),
+            removes_ring: "{player} removes a ring.".to_owned(),
+            wins: "{player} wins!".to_owned(),
+            draw: "Draw.".to_owned(),
+            markers_left: "markers left: {count}".to_owned(),
+            tooltip_place_ring: "Place ring".to_owned(),
+            tooltip_place_marker: "Place marker and move this ring".to_owned(),
+            tooltip_move_ring: "Move ring here".to_owned(),
+            tooltip_flips_one: "Move ring here, flips 1 marker".to_owned(),
+            tooltip_flips: "Move ring here, flips {count} markers".to_owned(),
+            tooltip_remove_run: "Remove run".to_owned(),
+            tooltip_remove_ring: "Remove ring and score".to_owned(),
+            note_wins: "Removing this ring wins the game".to_owned(),
+            note_scores: "Scores point {point} of {total}".to_owned(),
+        }
+    }
+}
+
+impl Strings {
+    pub fn player(&self, player: Player) -> &str {
+        match player {
+            Player::White => &self.white,
+            Player::Black => &self.black,
+        }
+    }
+
+    pub fn fill(&self, template: &str, player: Player) -> String {
+        template.replace("{player}", self.player(player))
+    }
+
+    // Replaces each "{name}" of `values` with its number.
+    pub fn fill_numbers(template: &str, values: &[(&str, usize)]) -> String {
+        values.iter().fold(template.to_owned(), |text, (name, n)| {
+            text.replace(&format!("{{{}}}", name), &n.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fill_uses_player_names() {
+        let strings = Strings {
+            white: "Weiß".to_owned(),
+            wins: "{player} gewinnt!".to_owned(),
+            ..Strings::default()
+        };
+        assert_eq!(strings.fill(&strings.wins, Player::White), "Weiß gewinnt!");
+        assert_eq!(
+            Strings::default().fill(&strings.removes_run, Player::Black),
+            "Black removes a run."
+        );
+
+        let strings = Strings {
+            tooltip_flips: "Ring hierher, dreht {count} Steine".to_owned(),
+            note_scores: "Punkt {point} von {total}".to_owned(),
+            ..Strings::default()
+        };
+        assert_eq!(
+            Strings::fill_numbers(&strings.tooltip_flips, &[("count", 3)]),
+            "Ring hierher, dreht 3 Steine"
+        );
+        assert_eq!(
+            Strings::fill_numbers(&strings.note_scores, &[("point", 2), ("total", 3)]),
+            "Punkt 2 von 3"
+        );
+    }
+}
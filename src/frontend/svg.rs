@@ -0,0 +1,180 @@
+use std::fmt::Write;
+
+use crate::common::coord::{HexCoord, Point};
+use crate::core::board::Board;
+use crate::core::entities::{Piece, Player};
+
+use super::primitives::build_grid_lines;
+
+// Colors are plain SVG color strings, sizes are in board units like TokenConfig.
+pub struct Theme {
+    pub background: String,
+    pub grid: String,
+    pub white: String,
+    pub black: String,
+    pub outline: String,
+    pub run_highlight: String,
+    pub ring_inner_radius: f32,
+    pub ring_outer_radius: f32,
+    pub marker_radius: f32,
+    pub highlight_runs: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: "lightgray".to_owned(),
+            grid: "dimgray".to_owned(),
+            white: "white".to_owned(),
+            black: "black".to_owned(),
+            outline: "black".to_owned(),
+            run_highlight: "orange".to_owned(),
+            ring_inner_radius: 0.2,
+            ring_outer_radius: 0.5,
+            marker_radius: 0.2,
+            highlight_runs: false,
+        }
+    }
+}
+
+impl Theme {
+    fn player_color(&self, player: Player) -> &str {
+        match player {
+            Player::White => &self.white,
+            Player::Black => &self.black,
+        }
+    }
+}
+
+// Point has y pointing up like the game window, SVG has it pointing down. Adding
+// 0 turns the -0 of the center row into 0.
+fn svg_xy(p: Point) -> (f32, f32) {
+    (p.0, -p.1 + 0.)
+}
+
+impl Board {
+    pub fn to_svg(&self, theme: &Theme) -> String {
+        let r = self.get_radius() + 1.;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            -r,
+            -r,
+            2. * r,
+            2. * r
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            -r,
+            -r,
+            2. * r,
+            2. * r,
+            theme.background
+        );
+
+        for [p0, p1] in build_grid_lines(self.get_radius()) {
+            // the grid lines are already in world coordinates
+            let (p0, p1) = (svg_xy(Point(p0.0, p0.1)), svg_xy(Point(p1.0, p1.1)));
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="0.02"/>"#,
+                p0.0, p0.1, p1.0, p1.1, theme.grid
+            );
+        }
+
+        if theme.highlight_runs {
+            for player in Player::all() {
+                for run in self.maximal_runs(&player) {
+                    let p0 = svg_xy(Point::from(run[0]));
+                    let p1 = svg_xy(Point::from(*run.last().unwrap()));
+                    let _ = writeln!(
+                        svg,
+                        r#"<line class="run" x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="0.5" stroke-linecap="round" opacity="0.5"/>"#,
+                        p0.0, p0.1, p1.0, p1.1, theme.run_highlight
+                    );
+                }
+            }
+        }
+
+        // sorted so the output is stable for identical boards
+        let mut pieces: Vec<(HexCoord, Piece)> = self.iter_pieces().collect();
+        pieces.sort_by_key(|(c, _)| (c.0, c.1));
+        for (c, piece) in pieces {
+            let pt = svg_xy(Point::from(c));
+            match piece {
+                Piece::Ring(player) => {
+                    let radius = 0.5 * (theme.ring_inner_radius + theme.ring_outer_radius);
+                    let width = theme.ring_outer_radius - theme.ring_inner_radius;
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle class="ring" cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                        pt.0,
+                        pt.1,
+                        radius,
+                        theme.player_color(player),
+                        width
+                    );
+                }
+                Piece::Marker(player) => {
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle class="marker" cx="{}" cy="{}" r="{}" fill="{}" stroke="{}" stroke-width="0.02"/>"#,
+                        pt.0,
+                        pt.1,
+                        theme.marker_radius,
+                        theme.player_color(player),
+                        theme.outline
+                    );
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_svg_piece_counts() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Ring(Player::White), &HexCoord::new(0, 1));
+        board.place_unchecked(&Piece::Ring(Player::Black), &HexCoord::new(2, 1));
+        for y in -2..3 {
+            board.place_unchecked(&Piece::Marker(Player::Black), &HexCoord::new(-1, y));
+        }
+
+        let svg = board.to_svg(&Theme::default());
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<circle").count(), 7);
+        assert_eq!(svg.matches(r#"class="ring""#).count(), 2);
+        assert_eq!(svg.matches(r#"class="marker""#).count(), 5);
+        assert_eq!(svg.matches(r#"class="run""#).count(), 0);
+
+        let theme = Theme {
+            highlight_runs: true,
+            ..Theme::default()
+        };
+        assert_eq!(board.to_svg(&theme).matches(r#"class="run""#).count(), 1);
+    }
+
+    #[test]
+    fn test_svg_top_field_is_at_the_top() {
+        let mut board = Board::new();
+        board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 4));
+        let svg = board.to_svg(&Theme::default());
+        let marker = svg.lines().find(|l| l.contains(r#"class="marker""#)).unwrap();
+        let cy: f32 = marker
+            .split(r#"cy=""#)
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(cy < 0., "{}", marker);
+    }
+}
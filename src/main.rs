@@ -2,20 +2,18 @@ pub mod common;
 pub mod core;
 pub mod frontend;
 
-use crate::core::entities::Piece;
-
 use crate::core::board::Board;
-use crate::core::entities::Player;
-use crate::core::game::Game;
+use crate::core::config::GameConfig;
+use crate::core::game::GameBuilder;
+use crate::frontend::frontend::Frontend;
+use crate::frontend::strings::Strings;
 
-use common::coord::HexCoord;
-use frontend::frontend::Frontend;
 use macroquad::prelude::*;
 use macroquad::window::Conf;
 
 fn window_conf() -> Conf {
     Conf {
-        window_title: "yinsh".to_owned(),
+        window_title: Strings::default().title,
         window_width: 1024,
         window_height: 1024,
         high_dpi: true,
@@ -26,16 +24,14 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let mut board = Board::new();
-
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(-2, 0));
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(-1, 0));
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(0, 0));
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(1, 0));
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(2, 0));
-    board.place_unchecked(&Piece::Marker(Player::White), &HexCoord::new(3, 0));
-    let mut frontend = Frontend::new(&board, 1024, 1024, 1., 1.);
-    let mut game = Game::new(Player::White, Box::new(frontend), board);
+    let config = GameConfig::default();
+    let board = Board::from_config(&config).expect("the default config is valid");
+    let frontend = Frontend::new(&board, 1024, 1024, 1., 1.)
+        .with_own_move_animation(true);
+    let mut game = GameBuilder::new(Box::new(frontend))
+        .config(config)
+        .build()
+        .expect("the empty board is a valid start");
 
     loop {
         game.tick();